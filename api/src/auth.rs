@@ -0,0 +1,282 @@
+use crate::{error::Error, Ctx, Result};
+use anyhow::Context;
+use axum::{
+    extract::{Request, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use time::{Date, OffsetDateTime};
+
+const API_KEY_HEADER: &str = "x-api-key";
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// API keys file, e.g.
+///
+/// ```toml
+/// [[keys]]
+/// name = "dashboard"
+/// # echo -n "$API_KEY" | sha256sum
+/// sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+/// requests_per_minute = 120
+/// chat_daily_quota = 100
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeysFile {
+    #[serde(default)]
+    keys: Vec<ApiKey>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKey {
+    pub name: String,
+
+    /// Hex-encoded SHA-256 of the key, so keys aren't stored in plaintext
+    sha256: String,
+
+    /// Allows access to `/v1/admin` endpoints
+    #[serde(default)]
+    pub admin: bool,
+
+    #[serde(default = "default_requests_per_minute")]
+    requests_per_minute: u32,
+    daily_quota: Option<u32>,
+
+    /// Chat requests are also limited separately since they proxy to a paid LLM
+    #[serde(default = "default_chat_requests_per_minute")]
+    chat_requests_per_minute: u32,
+    #[serde(default = "default_chat_daily_quota")]
+    chat_daily_quota: Option<u32>,
+}
+
+fn default_requests_per_minute() -> u32 {
+    120
+}
+
+fn default_chat_requests_per_minute() -> u32 {
+    6
+}
+
+fn default_chat_daily_quota() -> Option<u32> {
+    Some(200)
+}
+
+/// Configured API keys along with their usage for rate limiting
+pub struct ApiKeys {
+    keys: HashMap<String, Arc<ApiKey>>,
+    usage: Mutex<HashMap<String, Usage>>,
+}
+
+#[derive(Default)]
+struct Usage {
+    requests: Counter,
+    chat: Counter,
+}
+
+/// Fixed window counters for per-minute limits and daily quotas
+struct Counter {
+    window_start: Instant,
+    window_count: u32,
+    day: Date,
+    day_count: u32,
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Counter {
+            window_start: Instant::now(),
+            window_count: 0,
+            day: OffsetDateTime::now_utc().date(),
+            day_count: 0,
+        }
+    }
+}
+
+impl Counter {
+    /// Checks a request would be within the limits, without counting it
+    fn check(&mut self, per_minute: u32, daily_quota: Option<u32>) -> Result<()> {
+        if self.window_start.elapsed() >= RATE_WINDOW {
+            self.window_start = Instant::now();
+            self.window_count = 0;
+        }
+        let today = OffsetDateTime::now_utc().date();
+        if self.day != today {
+            self.day = today;
+            self.day_count = 0;
+        }
+
+        if self.window_count >= per_minute {
+            return Err(Error::too_many(
+                "rate limit exceeded, try again in a minute",
+            ));
+        }
+        if daily_quota.is_some_and(|quota| self.day_count >= quota) {
+            return Err(Error::too_many("daily quota exceeded"));
+        }
+        Ok(())
+    }
+
+    fn increment(&mut self) {
+        self.window_count += 1;
+        self.day_count += 1;
+    }
+}
+
+impl ApiKeys {
+    pub fn load(path: &Path) -> anyhow::Result<ApiKeys> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading API keys from {}", path.display()))?;
+        let file: KeysFile = toml::from_str(&contents)
+            .with_context(|| format!("parsing API keys from {}", path.display()))?;
+
+        // Names attribute usage and conversations, so each key needs its own
+        let mut keys = HashMap::new();
+        let mut names = HashSet::new();
+        for mut key in file.keys {
+            key.sha256 = key.sha256.to_lowercase();
+            if !names.insert(key.name.clone()) {
+                anyhow::bail!("duplicate API key name {} in {}", key.name, path.display());
+            }
+            if keys.contains_key(&key.sha256) {
+                anyhow::bail!("API key {} reuses another key's sha256", key.name);
+            }
+            keys.insert(key.sha256.clone(), Arc::new(key));
+        }
+        Ok(ApiKeys {
+            keys,
+            usage: Mutex::default(),
+        })
+    }
+
+    fn authenticate(&self, key: &str) -> Result<Arc<ApiKey>> {
+        let hash = format!("{:x}", Sha256::digest(key.as_bytes()));
+        self.keys.get(&hash).cloned().ok_or(Error::Unauthorized)
+    }
+
+    /// Counts a request against the key's limits, or rejects it without counting anything
+    fn check_limits(&self, key: &ApiKey, chat: bool) -> Result<()> {
+        let mut usage = self.usage.lock().expect("usage lock poisoned");
+        let usage = usage.entry(key.sha256.clone()).or_default();
+        usage
+            .requests
+            .check(key.requests_per_minute, key.daily_quota)?;
+        if chat {
+            usage
+                .chat
+                .check(key.chat_requests_per_minute, key.chat_daily_quota)?;
+            usage.chat.increment();
+        }
+        usage.requests.increment();
+        Ok(())
+    }
+}
+
+/// Endpoints that call out to the LLM
+fn is_chat(path: &str) -> bool {
+    path.starts_with("/v1/chat/") || path == "/v1/ask"
+}
+
+fn request_key(headers: &HeaderMap) -> Option<&str> {
+    if let Some(key) = headers.get(API_KEY_HEADER) {
+        return key.to_str().ok();
+    }
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// Middleware requiring a valid API key and enforcing its rate limits
+///
+/// Does nothing if no API keys are configured, which needs `AUTH_DISABLED`.
+pub async fn require_api_key(
+    State(ctx): State<Ctx>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response> {
+    let Some(api_keys) = &ctx.api_keys else {
+        return Ok(next.run(request).await);
+    };
+
+    let key = request_key(request.headers()).ok_or(Error::Unauthorized)?;
+    let key = api_keys.authenticate(key)?;
+    tracing::Span::current().record("user", key.name.as_str());
+
+    let path = request.uri().path();
+    if path.starts_with("/v1/admin") && !key.admin {
+        return Err(Error::Forbidden);
+    }
+    api_keys.check_limits(&key, is_chat(path))?;
+
+    // Lets handlers attribute what's created to the key
+    request.extensions_mut().insert(key);
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_keys(keys: &str) -> ApiKeys {
+        let file: KeysFile = toml::from_str(keys).unwrap();
+        let keys = file
+            .keys
+            .into_iter()
+            .map(|key| (key.sha256.clone(), Arc::new(key)))
+            .collect();
+        ApiKeys {
+            keys,
+            usage: Mutex::default(),
+        }
+    }
+
+    #[test]
+    fn rejected_requests_use_no_chat_quota() {
+        let api_keys = api_keys(
+            r#"
+            [[keys]]
+            name = "dashboard"
+            sha256 = "a"
+            requests_per_minute = 1
+            chat_requests_per_minute = 10
+            "#,
+        );
+        let key = api_keys.keys["a"].clone();
+        api_keys.check_limits(&key, false).unwrap();
+        assert!(api_keys.check_limits(&key, true).is_err());
+
+        let usage = api_keys.usage.lock().unwrap();
+        assert_eq!(usage["a"].chat.day_count, 0);
+        assert_eq!(usage["a"].requests.day_count, 1);
+    }
+
+    #[test]
+    fn limits_keys_separately() {
+        let api_keys = api_keys(
+            r#"
+            [[keys]]
+            name = "first"
+            sha256 = "a"
+            requests_per_minute = 1
+
+            [[keys]]
+            name = "second"
+            sha256 = "b"
+            requests_per_minute = 1
+            "#,
+        );
+        let (first, second) = (api_keys.keys["a"].clone(), api_keys.keys["b"].clone());
+        api_keys.check_limits(&first, false).unwrap();
+        api_keys.check_limits(&second, false).unwrap();
+        assert!(api_keys.check_limits(&first, false).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::telemetry;
+use crate::v1::JsonRecords;
+use regex::Regex;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+static DATASET_YEAR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:pbp|roster)(\d{4})\b").expect("valid regex"));
+
+/// Cache of query results keyed by normalized SQL
+///
+/// Results that touch the current season's datasets expire after a short TTL since
+/// those datasets are refreshed during the season. Results only touching past seasons
+/// are kept until evicted by the size limit, which is measured in rows. Empty results count as
+/// one row so they're limited too.
+#[derive(Clone)]
+pub struct QueryCache {
+    cache: moka::future::Cache<String, CachedRecords>,
+    current_year: u16,
+    current_ttl: Duration,
+    stats: Arc<CacheStats>,
+}
+
+#[derive(Clone)]
+struct CachedRecords {
+    records: Arc<JsonRecords>,
+    years: Vec<u16>,
+    ttl: Option<Duration>,
+}
+
+#[derive(Default)]
+struct CacheStats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct DatasetExpiry;
+
+impl moka::Expiry<String, CachedRecords> for DatasetExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &String,
+        value: &CachedRecords,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        value.ttl
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CacheMetrics {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: u64,
+    pub rows: u64,
+}
+
+impl QueryCache {
+    pub fn new(current_year: u16, current_ttl: Duration, max_rows: u64) -> Self {
+        let cache = moka::future::Cache::builder()
+            .max_capacity(max_rows)
+            .weigher(|_key, value: &CachedRecords| {
+                u32::try_from(value.records.len().max(1)).unwrap_or(u32::MAX)
+            })
+            .expire_after(DatasetExpiry)
+            .support_invalidation_closures()
+            .build();
+
+        QueryCache {
+            cache,
+            current_year,
+            current_ttl,
+            stats: Arc::default(),
+        }
+    }
+
+    pub async fn get(&self, query: &str) -> Option<JsonRecords> {
+        match self.cache.get(&normalize(query)).await {
+            Some(cached) => {
+                self.stats.hits.fetch_add(1, Ordering::Relaxed);
+                telemetry::record_cache_lookup(true);
+                Some(cached.records.as_ref().clone())
+            }
+            None => {
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                telemetry::record_cache_lookup(false);
+                None
+            }
+        }
+    }
+
+    pub async fn insert(&self, query: &str, records: &JsonRecords) {
+        let years = dataset_years(query);
+        let ttl = years
+            .contains(&self.current_year)
+            .then_some(self.current_ttl);
+        let cached = CachedRecords {
+            records: Arc::new(records.clone()),
+            years,
+            ttl,
+        };
+        self.cache.insert(normalize(query), cached).await;
+    }
+
+    /// Invalidates cached results for a season's datasets, or everything if `year` is `None`
+    pub fn invalidate(&self, year: Option<u16>) -> anyhow::Result<()> {
+        match year {
+            None => self.cache.invalidate_all(),
+            Some(year) => {
+                self.cache
+                    .invalidate_entries_if(move |_key, value| value.years.contains(&year))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn metrics(&self) -> CacheMetrics {
+        CacheMetrics {
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            misses: self.stats.misses.load(Ordering::Relaxed),
+            entries: self.cache.entry_count(),
+            rows: self.cache.weighted_size(),
+        }
+    }
+}
+
+/// Collapses whitespace so formatting differences in generated SQL share a cache entry
+fn normalize(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Seasons of the `pbp{year}` and `roster{year}` datasets referenced by a query
+fn dataset_years(query: &str) -> Vec<u16> {
+    let mut years: Vec<u16> = DATASET_YEAR
+        .captures_iter(query)
+        .filter_map(|cap| cap[1].parse().ok())
+        .collect();
+    years.sort_unstable();
+    years.dedup();
+    years
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn empty_results_count_against_the_limit() {
+        let cache = QueryCache::new(2024, Duration::from_secs(60), 2);
+        for player in ["a", "b", "c"] {
+            let query = format!("SELECT * FROM pbp2023 WHERE player = '{player}'");
+            cache.insert(&query, &JsonRecords::new()).await;
+        }
+        cache.cache.run_pending_tasks().await;
+        assert_eq!(cache.metrics().entries, 2);
+    }
+}
@@ -1,4 +1,7 @@
+use crate::serde_utils;
+use fff::years::Years;
 use serde::Deserialize;
+use std::path::PathBuf;
 use url::Url;
 
 /// Configuration parameters for the application
@@ -19,4 +22,84 @@ pub struct Config {
     /// Spice URLs for querying data and models
     pub spice_http_url: Url,
     pub spice_flight_url: Url,
+
+    /// Maximum number of concurrent Spice Flight connections
+    #[serde(default = "default_spice_pool_size")]
+    pub spice_pool_size: usize,
+
+    /// Attempts to reach Spice at startup before serving in a degraded state
+    #[serde(default = "default_spice_startup_attempts")]
+    pub spice_startup_attempts: u32,
+
+    /// Seconds to cache query results for the current season (past seasons don't expire)
+    #[serde(default = "default_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+
+    /// Maximum number of result rows held in the query cache
+    #[serde(default = "default_cache_max_rows")]
+    pub cache_max_rows: u64,
+
+    /// TOML file of hashed API keys and their rate limits
+    ///
+    /// Required unless `auth_disabled` is set.
+    pub api_keys_file: Option<PathBuf>,
+
+    /// Serves without API keys when `api_keys_file` is unset, only meant for local development
+    #[serde(default)]
+    pub auth_disabled: bool,
+
+    /// Seasons with `pbp{year}` and `roster{year}` datasets in the spicepod (e.g. `2023-2024`)
+    #[serde(with = "serde_utils::string", default = "default_years")]
+    pub years: Years,
+
+    /// Spice model that interprets `/v1/ask` questions, must support structured outputs
+    #[serde(default = "default_ask_model")]
+    pub ask_model: String,
+
+    /// Fantasy scoring described to the chat model: `standard`, `half_ppr`, `ppr`
+    /// or the name of a config in `scoring_dir`
+    #[serde(default = "default_scoring")]
+    pub scoring: String,
+
+    /// Directory of TOML scoring configs, selectable by file name with `scoring=`
+    pub scoring_dir: Option<PathBuf>,
+
+    /// Directory of `player_id,points` CSV projections, selectable by file name with `projections=`
+    pub projections_dir: Option<PathBuf>,
+
+    /// SQLite file where chat conversations are stored, created if missing
+    #[serde(default = "default_conversations_db")]
+    pub conversations_db: PathBuf,
+}
+
+fn default_spice_pool_size() -> usize {
+    4
+}
+
+fn default_spice_startup_attempts() -> u32 {
+    8
+}
+
+fn default_cache_ttl_secs() -> u64 {
+    300
+}
+
+fn default_cache_max_rows() -> u64 {
+    200_000
+}
+
+fn default_years() -> Years {
+    Years::new(2023, 2024)
+}
+
+fn default_scoring() -> String {
+    "ppr".to_string()
+}
+
+fn default_ask_model() -> String {
+    "openai-with-fff".to_string()
+}
+
+fn default_conversations_db() -> PathBuf {
+    PathBuf::from("conversations.db")
 }
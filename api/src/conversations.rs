@@ -0,0 +1,326 @@
+use anyhow::Context;
+use parse_display::{Display, FromStr};
+use rusqlite::{params, types::Type, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS conversations (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    created_by TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    role TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS messages_conversation ON messages (conversation_id, id);
+";
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Conversation {
+    pub id: Uuid,
+    pub title: String,
+    /// Name of the API key that started the conversation
+    pub created_by: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schema(value_type = String)]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schema(value_type = String)]
+    pub updated_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, FromStr, Serialize, Deserialize, ToSchema)]
+#[display(style = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Message {
+    pub id: i64,
+    pub role: Role,
+    pub content: String,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schema(value_type = String)]
+    pub created_at: OffsetDateTime,
+}
+
+/// Chat conversations persisted in a SQLite file
+///
+/// SQLite calls are blocking, so each one runs on tokio's blocking thread pool.
+/// Conversations are only visible to their `owner`, the name of the API key that created them,
+/// or `None` when authentication is disabled.
+#[derive(Clone)]
+pub struct ConversationStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl ConversationStore {
+    pub fn open(path: &Path) -> anyhow::Result<ConversationStore> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("opening conversations db {}", path.display()))?;
+        conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA journal_mode = WAL;")?;
+        conn.execute_batch(SCHEMA)
+            .context("creating conversations schema")?;
+        Ok(ConversationStore {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Runs `f` with the connection on the blocking thread pool
+    async fn with_conn<T, F>(&self, f: F) -> anyhow::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().expect("conversations lock poisoned");
+            f(&mut conn)
+        })
+        .await?
+        .context("conversations query")
+    }
+
+    pub async fn create(
+        &self,
+        title: String,
+        created_by: Option<String>,
+    ) -> anyhow::Result<Conversation> {
+        let now = now();
+        let conversation = Conversation {
+            id: Uuid::new_v4(),
+            title,
+            created_by,
+            created_at: now,
+            updated_at: now,
+        };
+        let row = conversation.clone();
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO conversations (id, title, created_by, created_at, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?4)",
+                params![
+                    row.id.to_string(),
+                    row.title,
+                    row.created_by,
+                    row.created_at.unix_timestamp()
+                ],
+            )
+        })
+        .await?;
+        Ok(conversation)
+    }
+
+    /// Conversations, most recently updated first
+    pub async fn list(
+        &self,
+        owner: Option<String>,
+        limit: u32,
+        offset: u32,
+    ) -> anyhow::Result<Vec<Conversation>> {
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, title, created_by, created_at, updated_at FROM conversations
+                WHERE created_by IS ?1
+                ORDER BY updated_at DESC, id LIMIT ?2 OFFSET ?3",
+            )?;
+            let rows = stmt.query_map(params![owner, limit, offset], conversation_from_row)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    pub async fn get(
+        &self,
+        id: Uuid,
+        owner: Option<String>,
+    ) -> anyhow::Result<Option<Conversation>> {
+        self.with_conn(move |conn| {
+            conn.query_row(
+                "SELECT id, title, created_by, created_at, updated_at FROM conversations
+                WHERE id = ?1 AND created_by IS ?2",
+                params![id.to_string(), owner],
+                conversation_from_row,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    pub async fn messages(&self, id: Uuid) -> anyhow::Result<Vec<Message>> {
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, role, content, created_at FROM messages
+                WHERE conversation_id = ?1 ORDER BY id",
+            )?;
+            let rows = stmt.query_map(params![id.to_string()], message_from_row)?;
+            rows.collect()
+        })
+        .await
+    }
+
+    /// Renames a conversation, returning false if `owner` has none with that id
+    pub async fn rename(
+        &self,
+        id: Uuid,
+        owner: Option<String>,
+        title: String,
+    ) -> anyhow::Result<bool> {
+        let updated = self
+            .with_conn(move |conn| {
+                conn.execute(
+                    "UPDATE conversations SET title = ?3, updated_at = ?4
+                    WHERE id = ?1 AND created_by IS ?2",
+                    params![id.to_string(), owner, title, now().unix_timestamp()],
+                )
+            })
+            .await?;
+        Ok(updated > 0)
+    }
+
+    /// Deletes a conversation and its messages, returning false if `owner` has none with that id
+    pub async fn delete(&self, id: Uuid, owner: Option<String>) -> anyhow::Result<bool> {
+        let deleted = self
+            .with_conn(move |conn| {
+                conn.execute(
+                    "DELETE FROM conversations WHERE id = ?1 AND created_by IS ?2",
+                    params![id.to_string(), owner],
+                )
+            })
+            .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Appends messages to a conversation, returning false if `owner` has none with that id
+    pub async fn append(
+        &self,
+        id: Uuid,
+        owner: Option<String>,
+        messages: Vec<(Role, String)>,
+    ) -> anyhow::Result<bool> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let now = now().unix_timestamp();
+            let updated = tx.execute(
+                "UPDATE conversations SET updated_at = ?3 WHERE id = ?1 AND created_by IS ?2",
+                params![id.to_string(), owner, now],
+            )?;
+            if updated == 0 {
+                return Ok(false);
+            }
+            for (role, content) in messages {
+                tx.execute(
+                    "INSERT INTO messages (conversation_id, role, content, created_at)
+                    VALUES (?1, ?2, ?3, ?4)",
+                    params![id.to_string(), role.to_string(), content, now],
+                )?;
+            }
+            tx.commit()?;
+            Ok(true)
+        })
+        .await
+    }
+}
+
+fn now() -> OffsetDateTime {
+    // Stored with second precision, so truncate to match what's read back
+    OffsetDateTime::now_utc()
+        .replace_nanosecond(0)
+        .expect("0 is a valid nanosecond")
+}
+
+fn conversion_err(
+    idx: usize,
+    ty: Type,
+    err: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(idx, ty, err.into())
+}
+
+fn timestamp(row: &Row, idx: usize) -> rusqlite::Result<OffsetDateTime> {
+    let secs: i64 = row.get(idx)?;
+    OffsetDateTime::from_unix_timestamp(secs).map_err(|err| conversion_err(idx, Type::Integer, err))
+}
+
+fn conversation_from_row(row: &Row) -> rusqlite::Result<Conversation> {
+    let id: String = row.get(0)?;
+    Ok(Conversation {
+        id: id
+            .parse()
+            .map_err(|err| conversion_err(0, Type::Text, err))?,
+        title: row.get(1)?,
+        created_by: row.get(2)?,
+        created_at: timestamp(row, 3)?,
+        updated_at: timestamp(row, 4)?,
+    })
+}
+
+fn message_from_row(row: &Row) -> rusqlite::Result<Message> {
+    let role: String = row.get(1)?;
+    Ok(Message {
+        id: row.get(0)?,
+        role: role
+            .parse()
+            .map_err(|err| conversion_err(1, Type::Text, err))?,
+        content: row.get(2)?,
+        created_at: timestamp(row, 3)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> ConversationStore {
+        ConversationStore::open(Path::new(":memory:")).unwrap()
+    }
+
+    fn owner(name: &str) -> Option<String> {
+        Some(name.to_string())
+    }
+
+    #[tokio::test]
+    async fn lists_only_owned_conversations() {
+        let store = store();
+        let mine = store.create("mine".into(), owner("a")).await.unwrap();
+        store.create("theirs".into(), owner("b")).await.unwrap();
+        store.create("open".into(), None).await.unwrap();
+
+        let listed = store.list(owner("a"), 50, 0).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, mine.id);
+        assert_eq!(store.list(None, 50, 0).await.unwrap()[0].title, "open");
+    }
+
+    #[tokio::test]
+    async fn hides_conversations_from_other_keys() {
+        let store = store();
+        let id = store.create("mine".into(), owner("a")).await.unwrap().id;
+        let message = vec![(Role::User, "hi".to_string())];
+
+        assert!(store.get(id, owner("b")).await.unwrap().is_none());
+        assert!(store.get(id, None).await.unwrap().is_none());
+        assert!(!store.rename(id, owner("b"), "x".into()).await.unwrap());
+        assert!(!store.append(id, owner("b"), message.clone()).await.unwrap());
+        assert!(!store.delete(id, owner("b")).await.unwrap());
+
+        assert!(store.append(id, owner("a"), message).await.unwrap());
+        assert_eq!(store.messages(id).await.unwrap().len(), 1);
+        assert!(store.delete(id, owner("a")).await.unwrap());
+    }
+}
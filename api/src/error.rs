@@ -1,5 +1,7 @@
+use async_openai::error::OpenAIError;
 use axum::response::{IntoResponse, Response};
 use axum::{http, Json};
+use fff::pbp::UnknownStat;
 use maglev::auth::AuthError;
 use maglev::error::google::ErrorResponse;
 use std::borrow::Cow;
@@ -28,6 +30,11 @@ pub enum Error {
     #[http_error(NOT_FOUND)]
     NotFound,
 
+    /// Return `429 Too Many Requests`
+    #[error("too many requests: {message}")]
+    #[http_error(TOO_MANY_REQUESTS, "{message}")]
+    TooManyRequests { message: Cow<'static, str> },
+
     /// Return `400 Bad Request`
     ///
     /// This also serializes the `errors` map to JSON
@@ -35,6 +42,11 @@ pub enum Error {
     #[http_error(BAD_REQUEST, "{message}")]
     BadRequest { message: Cow<'static, str> },
 
+    /// Return `502 Bad Gateway` when an upstream service (e.g. the LLM) fails or is unreachable
+    #[error("bad gateway: {message}")]
+    #[http_error(BAD_GATEWAY, "{message}")]
+    BadGateway { message: Cow<'static, str> },
+
     /// Return `500 Internal Server Error` on a `anyhow::Error`.
     ///
     /// `anyhow::Error` is used in a few places to capture context and backtraces
@@ -78,14 +90,72 @@ impl From<AuthError> for Error {
     }
 }
 
+impl From<UnknownStat> for Error {
+    fn from(err: UnknownStat) -> Self {
+        Error::bad_req(err.to_string())
+    }
+}
+
+impl From<OpenAIError> for Error {
+    fn from(err: OpenAIError) -> Self {
+        match err {
+            OpenAIError::InvalidArgument(message) => Error::bad_req(message),
+            OpenAIError::ApiError(err)
+                if err.r#type.as_deref() == Some("invalid_request_error") =>
+            {
+                Error::bad_req(err.message)
+            }
+            OpenAIError::ApiError(err) => Error::BadGateway {
+                message: format!("model error: {}", err.message).into(),
+            },
+            OpenAIError::Reqwest(err) if err.is_connect() || err.is_timeout() => {
+                tracing::warn!("model unreachable: {err:?}");
+                Error::BadGateway {
+                    message: "model is unreachable".into(),
+                }
+            }
+            OpenAIError::StreamError(message) => {
+                tracing::warn!("model stream error: {message}");
+                match stream_status(&message) {
+                    Some(status) if status.is_client_error() => {
+                        Error::bad_req(format!("model rejected the request: {status}"))
+                    }
+                    Some(status) => Error::BadGateway {
+                        message: format!("model error: {status}").into(),
+                    },
+                    None => Error::BadGateway {
+                        message: "model stream failed".into(),
+                    },
+                }
+            }
+            err => anyhow::Error::new(err)
+                .context("Unexpected chat error")
+                .into(),
+        }
+    }
+}
+
+/// Reads the HTTP status out of a stream error, which only carries the upstream error's message
+fn stream_status(message: &str) -> Option<http::StatusCode> {
+    let code = message.strip_prefix("Invalid status code: ")?;
+    let code = code.split_whitespace().next()?.parse().ok()?;
+    http::StatusCode::from_u16(code).ok()
+}
+
 impl Error {
     /// Convenient constructor for `Error::UnprocessableEntity`.
     ///
     /// Multiple for the same key are collected into a list for that key
-    #[allow(unused)]
     pub fn bad_req(message: impl Into<Cow<'static, str>>) -> Self {
         Self::BadRequest {
             message: message.into(),
         }
     }
+
+    /// Convenient constructor for `Error::TooManyRequests`.
+    pub fn too_many(message: impl Into<Cow<'static, str>>) -> Self {
+        Self::TooManyRequests {
+            message: message.into(),
+        }
+    }
 }
@@ -0,0 +1,114 @@
+use crate::error::Error;
+use axum::{
+    async_trait,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::ops::Deref;
+
+/// Query string extractor that rejects invalid params with a `400 Bad Request`
+///
+/// Unlike `axum::extract::Query`, the error message names the param that failed
+/// to deserialize, e.g. "invalid query param `weeks`: ...".
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        serde_path_to_error::deserialize(deserializer)
+            .map(Query)
+            .map_err(|err| {
+                let param = err.path().to_string();
+                match param.as_str() {
+                    "." => Error::bad_req(format!("invalid query: {}", err.inner())),
+                    _ => Error::bad_req(format!("invalid query param `{param}`: {}", err.inner())),
+                }
+            })
+    }
+}
+
+impl<T> Deref for Query<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// JSON body extractor that rejects invalid bodies with a `400 Bad Request`
+///
+/// Unlike `axum::Json`, missing content types and malformed or mistyped bodies all become
+/// `Error::BadRequest`, so the client gets the API's usual error body. Also usable as a response.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        axum::Json::from_request(req, state)
+            .await
+            .map(|axum::Json(value)| Json(value))
+            .map_err(|rejection| Error::bad_req(format!("invalid body: {}", rejection.body_text())))
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::header::CONTENT_TYPE};
+    use serde_json::Value;
+
+    fn request(body: &str) -> Request {
+        Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_bodies_as_bad_requests() {
+        let Json(value) = Json::<Value>::from_request(request(r#"{"a": 1}"#), &())
+            .await
+            .unwrap();
+        assert_eq!(value["a"], 1);
+
+        for body in ["{", r#"["not", "a", "map"]"#] {
+            let err =
+                Json::<std::collections::BTreeMap<String, u8>>::from_request(request(body), &())
+                    .await
+                    .unwrap_err();
+            assert!(matches!(err, Error::BadRequest { .. }), "{err:?}");
+        }
+
+        let missing_type = Request::builder().body(Body::from("{}")).unwrap();
+        let err = Json::<Value>::from_request(missing_type, &())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::BadRequest { .. }), "{err:?}");
+    }
+}
@@ -1,70 +1,124 @@
 #![forbid(unsafe_code)]
 
 use anyhow::Context;
+use auth::ApiKeys;
 use axum::{
     extract::FromRef,
-    http::{header::AUTHORIZATION, HeaderValue},
+    http::{header::AUTHORIZATION, HeaderValue, StatusCode},
+    middleware,
     routing::get,
     Json, Router,
 };
+use cache::QueryCache;
 use config::Config;
+use conversations::ConversationStore;
+use futures::StreamExt;
+use itertools::Itertools;
 use maglev::EnvConfig;
+use metrics_exporter_prometheus::PrometheusHandle;
 use openai::OpenAiClient;
-use serde_json::{json, Value};
-use spiceai::ClientBuilder;
+use projections::ProjectionSets;
+use scoring::ScoringConfigs;
+use serde_json::{json, Map, Value};
+use spice::SpicePool;
 use std::{net::Ipv4Addr, sync::Arc, time::Duration};
-use tokio::sync::Mutex;
 use tower_http::{
     catch_panic::CatchPanicLayer, compression::CompressionLayer, cors,
     sensitive_headers::SetSensitiveHeadersLayer, timeout::TimeoutLayer, trace::TraceLayer,
 };
 
+mod auth;
+mod cache;
 mod config;
+mod conversations;
 mod error;
+mod extract;
 mod openai;
+mod projections;
+mod scoring;
 mod serde_utils;
+mod spice;
+mod telemetry;
 mod v1;
 
 pub(crate) type Result<T, E = error::Error> = std::result::Result<T, E>;
 
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Clone, FromRef)]
 pub struct Ctx {
     pub config: Arc<Config>,
-    pub spice: Arc<Mutex<spiceai::Client>>,
+    pub spice: SpicePool,
+    pub cache: QueryCache,
     pub openai: OpenAiClient,
+    pub conversations: ConversationStore,
+    pub scoring: Arc<ScoringConfigs>,
+    pub projections: Arc<ProjectionSets>,
+    pub api_keys: Option<Arc<ApiKeys>>,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
 
+    let metrics = telemetry::install()?;
     let config = Config::from_env()?;
     let port = config.port;
-    tracing::trace!(
-        "Initialize Spice client: {}",
-        config.spice_flight_url.as_str()
+    let spice = spice::pool(config.spice_flight_url.as_str(), config.spice_pool_size)?;
+    let spice_reachable = spice::wait_until_reachable(&spice, config.spice_startup_attempts).await;
+    if !spice_reachable {
+        tracing::warn!("Starting without spice, /health will report degraded");
+    }
+    let cache = QueryCache::new(
+        config.years.latest(),
+        Duration::from_secs(config.cache_ttl_secs),
+        config.cache_max_rows,
     );
-    let spice = ClientBuilder::new()
-        .flight_url(config.spice_flight_url.as_str())
-        .build()
-        .await
-        .expect("spice client initialized");
     let openai = OpenAiClient::with_base_url(config.spice_http_url.join("v1").unwrap().as_str());
+    let conversations = ConversationStore::open(&config.conversations_db)?;
+    let scoring = ScoringConfigs::load(config.scoring_dir.as_deref(), &config.scoring)?;
+    let projections = ProjectionSets::load(config.projections_dir.as_deref())?;
+    // Chat and admin endpoints cost money or change state, so only run open when asked to
+    let api_keys = match &config.api_keys_file {
+        Some(path) => Some(Arc::new(ApiKeys::load(path)?)),
+        None if config.auth_disabled => {
+            tracing::warn!("AUTH_DISABLED is set, API key authentication is disabled");
+            None
+        }
+        None => anyhow::bail!(
+            "API_KEYS_FILE is required, set AUTH_DISABLED=true to run without authentication"
+        ),
+    };
 
     let ctx = Ctx {
         config: Arc::new(config),
-        spice: Arc::new(Mutex::new(spice)),
+        spice,
+        cache,
         openai,
+        conversations,
+        scoring: Arc::new(scoring),
+        projections: Arc::new(projections),
+        api_keys,
     };
 
-    let app = api_router(ctx);
+    if spice_reachable {
+        match v1::schema::check_drift(&ctx).await {
+            Ok(0) => {}
+            Ok(problems) => {
+                tracing::warn!("{problems} schema problems found, run `melange doctor` for details")
+            }
+            Err(err) => tracing::warn!("schema drift check failed: {err}"),
+        }
+    }
+
+    let app = api_router(ctx, metrics);
     let addr = (Ipv4Addr::UNSPECIFIED, port);
     maglev::serve(addr, app)
         .await
         .context("error running HTTP server")
 }
 
-fn api_router(ctx: Ctx) -> Router {
+fn api_router(ctx: Ctx, metrics: PrometheusHandle) -> Router {
     let cors_layer = match &ctx.config.allow_origin {
         None => cors::CorsLayer::new(),
         Some(allow_origin) => {
@@ -82,8 +136,14 @@ fn api_router(ctx: Ctx) -> Router {
         }
     };
 
+    let probe_ctx = ctx.clone();
     Router::new()
         .merge(v1::router())
+        .route_layer(middleware::from_fn_with_state(
+            ctx.clone(),
+            auth::require_api_key,
+        ))
+        .route_layer(middleware::from_fn(telemetry::track_requests))
         .layer((
             SetSensitiveHeadersLayer::new([AUTHORIZATION]),
             CompressionLayer::new(),
@@ -95,10 +155,106 @@ fn api_router(ctx: Ctx) -> Router {
             CatchPanicLayer::new(),
         ))
         .with_state(ctx)
-        // Health endpoint without above middleware (i.e. disable tracing)
-        .route("/health", get(health))
+        // Health endpoints without above middleware (i.e. disable tracing)
+        .route("/health", get({
+            let ctx = probe_ctx.clone();
+            move || health(ctx)
+        }))
+        .route("/ready", get({
+            let ctx = probe_ctx.clone();
+            move || ready(ctx)
+        }))
+        .route(
+            "/metrics",
+            get(move || async move { telemetry::render(&metrics, &probe_ctx) }),
+        )
+}
+
+/// Liveness check that reports `degraded` (but still succeeds) when Spice is unreachable
+async fn health(ctx: Ctx) -> Json<Value> {
+    let spice_ok = tokio::time::timeout(PROBE_TIMEOUT, probe(&ctx, "SELECT 1")).await;
+    if spice_ok == Ok(true) {
+        Json(json!({"status": "ok"}))
+    } else {
+        Json(json!({"status": "degraded", "spice": "unreachable"}))
+    }
+}
+
+/// Readiness check that verifies every configured dataset can be queried
+///
+/// The whole check shares `PROBE_TIMEOUT`, so a busy pool fails readiness once rather than
+/// timing out queued probes one by one.
+async fn ready(ctx: Ctx) -> (StatusCode, Json<Value>) {
+    let datasets: Vec<String> = ctx
+        .config
+        .years
+        .iter()
+        .flat_map(|year| [format!("pbp{year}"), format!("roster{year}")])
+        .collect();
+
+    let Ok((spice_ok, available)) =
+        tokio::time::timeout(PROBE_TIMEOUT, probe_datasets(&ctx, &datasets)).await
+    else {
+        tracing::warn!("readiness check timed out after {PROBE_TIMEOUT:?}");
+        let body = json!({"status": "not_ready", "error": "readiness check timed out"});
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(body));
+    };
+
+    let ready = spice_ok && available.iter().all(|ok| *ok);
+    let datasets: Map<String, Value> = datasets
+        .into_iter()
+        .zip(available)
+        .map(|(dataset, ok)| (dataset, Value::Bool(ok)))
+        .collect();
+
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let body = json!({
+        "status": if ready { "ready" } else { "not_ready" },
+        "spice": spice_ok,
+        "datasets": datasets,
+    });
+    (status, Json(body))
 }
 
-async fn health() -> Json<Value> {
-    Json(json!({"status": "ok"}))
+/// Whether Spice is reachable and each dataset can be queried
+///
+/// Checks every dataset in one query, and only when that fails probes them separately to find
+/// the missing ones, a few at a time so requests still get connections from the pool.
+async fn probe_datasets(ctx: &Ctx, datasets: &[String]) -> (bool, Vec<bool>) {
+    let all = datasets
+        .iter()
+        .map(|dataset| format!("(SELECT 1 FROM {dataset} LIMIT 1)"))
+        .join(" UNION ALL ");
+    if probe(ctx, &all).await {
+        return (true, vec![true; datasets.len()]);
+    }
+    if !probe(ctx, "SELECT 1").await {
+        return (false, vec![false; datasets.len()]);
+    }
+
+    let concurrency = (ctx.config.spice_pool_size / 2).max(1);
+    let probes: Vec<_> = datasets
+        .iter()
+        .map(|dataset| format!("SELECT 1 FROM {dataset} LIMIT 1"))
+        .collect();
+    let available = futures::stream::iter(probes)
+        .map(|sql| probe(ctx, sql))
+        .buffered(concurrency)
+        .collect()
+        .await;
+    (true, available)
+}
+
+async fn probe(ctx: &Ctx, sql: impl AsRef<str>) -> bool {
+    match spice::probe(&ctx.spice, sql.as_ref()).await {
+        Ok(()) => true,
+        Err(err) => {
+            tracing::debug!("spice probe failed: {err:#}");
+            false
+        }
+    }
 }
@@ -72,7 +72,7 @@ pub fn user_message(msg: &str) -> ChatCompletionRequestMessage {
 
 pub fn system_message(msg: &str) -> ChatCompletionRequestMessage {
     ChatCompletionRequestMessage::System(ChatCompletionRequestSystemMessage {
-        content: msg.to_string(),
+        content: msg.into(),
         name: None,
     })
 }
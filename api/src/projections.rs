@@ -0,0 +1,50 @@
+use crate::{error::Error, Result};
+use anyhow::Context;
+use fff::tiers::Projections;
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Projected points selectable by name with `projections=`
+///
+/// These are the `*.csv` files of `player_id,points` in `PROJECTIONS_DIR`, named after the file.
+pub struct ProjectionSets {
+    sets: BTreeMap<String, Arc<Projections>>,
+}
+
+impl ProjectionSets {
+    /// Loads the projections in `dir`, failing on any invalid file so typos don't go unnoticed
+    pub fn load(dir: Option<&Path>) -> anyhow::Result<ProjectionSets> {
+        let mut sets = BTreeMap::new();
+        if let Some(dir) = dir {
+            let entries = std::fs::read_dir(dir)
+                .with_context(|| format!("reading projections from {}", dir.display()))?;
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "csv") {
+                    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                    let projections = Projections::load(&path)
+                        .with_context(|| format!("invalid projections {}", path.display()))?;
+                    tracing::info!("Loaded projections {name}");
+                    sets.insert(name.to_string(), Arc::new(projections));
+                }
+            }
+        }
+        Ok(ProjectionSets { sets })
+    }
+
+    /// Looks up a `projections=` param, `None` if the param wasn't given
+    pub fn resolve(&self, name: Option<&str>) -> Result<Option<Arc<Projections>>> {
+        let Some(name) = name else {
+            return Ok(None);
+        };
+        match self.sets.get(name) {
+            Some(projections) => Ok(Some(projections.clone())),
+            None => Err(Error::bad_req(format!(
+                "unknown projections: {name}, expected one of: {}",
+                self.sets.keys().join(", ")
+            ))),
+        }
+    }
+}
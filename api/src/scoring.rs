@@ -0,0 +1,75 @@
+use crate::{error::Error, Result};
+use anyhow::Context;
+use fff::scoring::Scoring;
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+const PRESETS: [&str; 3] = ["standard", "half_ppr", "ppr"];
+
+/// Scoring configs selectable by name with `scoring=`
+///
+/// These are the presets plus each `*.toml` file in `SCORING_DIR`, named after the file.
+pub struct ScoringConfigs {
+    configs: BTreeMap<String, Arc<Scoring>>,
+    /// Scoring described to the chat model
+    default: Arc<Scoring>,
+}
+
+impl ScoringConfigs {
+    /// Loads the configs in `dir`, failing on any invalid file so typos don't go unnoticed
+    pub fn load(dir: Option<&Path>, default: &str) -> anyhow::Result<ScoringConfigs> {
+        let mut configs: BTreeMap<_, _> = PRESETS
+            .into_iter()
+            .map(|name| (name.to_string(), Arc::new(name.parse::<Scoring>().unwrap())))
+            .collect();
+
+        if let Some(dir) = dir {
+            let entries = std::fs::read_dir(dir)
+                .with_context(|| format!("reading scoring configs from {}", dir.display()))?;
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "toml") {
+                    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                    let scoring = Scoring::load(&path)
+                        .with_context(|| format!("invalid scoring config {}", path.display()))?;
+                    tracing::info!("Loaded scoring config {name}: {scoring}");
+                    configs.insert(name.to_string(), Arc::new(scoring));
+                }
+            }
+        }
+
+        let Some(default) = configs.get(default).cloned() else {
+            anyhow::bail!(
+                "SCORING is {default}, expected one of: {}",
+                configs.keys().join(", ")
+            );
+        };
+        Ok(ScoringConfigs { configs, default })
+    }
+
+    pub fn default(&self) -> &Scoring {
+        &self.default
+    }
+
+    /// Looks up a `scoring=` param, `None` if the param wasn't given
+    pub fn resolve(&self, name: Option<&str>) -> Result<Option<Arc<Scoring>>> {
+        let Some(name) = name else {
+            return Ok(None);
+        };
+        match self.configs.get(name) {
+            Some(scoring) => Ok(Some(scoring.clone())),
+            None => Err(Error::bad_req(format!(
+                "unknown scoring: {name}, expected one of: {}",
+                self.configs.keys().join(", ")
+            ))),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Scoring)> {
+        self.configs
+            .iter()
+            .map(|(name, scoring)| (name.as_str(), scoring.as_ref()))
+    }
+}
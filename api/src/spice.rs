@@ -0,0 +1,302 @@
+use anyhow::{anyhow, Context};
+use arrow::array::RecordBatch;
+use deadpool::managed::{self, Metrics, RecycleResult};
+use deadpool::Runtime;
+use futures::{Stream, TryStreamExt};
+use spiceai::ClientBuilder;
+use std::time::Duration;
+
+/// Pool of Spice Flight clients so concurrent requests don't queue behind a single connection
+pub type SpicePool = managed::Pool<SpiceManager>;
+
+/// A Spice client checked out from the [`SpicePool`]
+pub type SpiceClient = managed::Object<SpiceManager>;
+
+/// Clients idle for longer than this are probed before being reused
+const HEALTH_CHECK_IDLE: Duration = Duration::from_secs(30);
+
+/// Longest delay between connection attempts at startup
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SpiceError {
+    #[error("failed to connect to spice: {0}")]
+    Connect(String),
+
+    #[error("spice health check failed: {0}")]
+    HealthCheck(String),
+}
+
+pub struct SpiceManager {
+    flight_url: String,
+}
+
+impl SpiceManager {
+    pub fn new(flight_url: &str) -> Self {
+        SpiceManager {
+            flight_url: flight_url.to_string(),
+        }
+    }
+}
+
+impl managed::Manager for SpiceManager {
+    type Type = spiceai::Client;
+    type Error = SpiceError;
+
+    async fn create(&self) -> Result<spiceai::Client, SpiceError> {
+        tracing::trace!("Initialize Spice client: {}", self.flight_url);
+        // Only flight is queried, so don't let the client connect to the cloud firecache
+        ClientBuilder::new()
+            .flight_url(&self.flight_url)
+            .firecache_url(&self.flight_url)
+            .build()
+            .await
+            .map_err(|err| SpiceError::Connect(err.to_string()))
+    }
+
+    async fn recycle(
+        &self,
+        client: &mut spiceai::Client,
+        metrics: &Metrics,
+    ) -> RecycleResult<SpiceError> {
+        if metrics.last_used() < HEALTH_CHECK_IDLE {
+            return Ok(());
+        }
+
+        tracing::trace!("Health checking idle spice client");
+        let flight = client
+            .query("SELECT 1")
+            .await
+            .map_err(|err| SpiceError::HealthCheck(err.to_string()))?;
+        flight
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(|err| SpiceError::HealthCheck(err.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Builds a pool of up to `size` clients
+///
+/// Clients are connected lazily, and any client that fails its health check is
+/// replaced with a new connection.
+pub fn pool(flight_url: &str, size: usize) -> anyhow::Result<SpicePool> {
+    let pool = SpicePool::builder(SpiceManager::new(flight_url))
+        .max_size(size)
+        .wait_timeout(Some(Duration::from_secs(10)))
+        .create_timeout(Some(Duration::from_secs(10)))
+        .recycle_timeout(Some(Duration::from_secs(5)))
+        .runtime(Runtime::Tokio1)
+        .build()?;
+    Ok(pool)
+}
+
+/// Starts a query on a pooled client and returns the stream of record batches
+///
+/// The client goes back to the pool as soon as the query has started. A client that
+/// fails to start a query is discarded so the next checkout reconnects. A query without rows
+/// yields one empty batch, so the result schema still reaches encoders like Arrow IPC.
+pub async fn query(
+    pool: &SpicePool,
+    query: &str,
+) -> anyhow::Result<impl Stream<Item = anyhow::Result<RecordBatch>> + Send + 'static> {
+    let mut client = pool.get().await.context("spice connection")?;
+    match client.query(query).await {
+        Ok(mut flight) => Ok(async_stream::try_stream! {
+            let mut empty = true;
+            while let Some(batch) = flight.try_next().await? {
+                empty = false;
+                yield batch;
+            }
+            if let Some(schema) = flight.schema().filter(|_| empty) {
+                yield RecordBatch::new_empty(schema.clone());
+            }
+        }),
+        Err(err) => {
+            drop(SpiceClient::take(client));
+            Err(anyhow!("Query error: {}", err))
+        }
+    }
+}
+
+/// Runs a query to completion, discarding the results
+pub async fn probe(pool: &SpicePool, sql: &str) -> anyhow::Result<()> {
+    let flight = query(pool, sql).await?;
+    flight.try_collect::<Vec<_>>().await?;
+    Ok(())
+}
+
+/// Waits for Spice to accept queries, retrying with exponential backoff
+///
+/// Returns false if Spice is still unreachable after `max_attempts`.
+pub async fn wait_until_reachable(pool: &SpicePool, max_attempts: u32) -> bool {
+    let mut delay = Duration::from_secs(1);
+    for attempt in 1..=max_attempts {
+        match probe(pool, "SELECT 1").await {
+            Ok(()) => return true,
+            Err(err) if attempt < max_attempts => {
+                tracing::warn!(
+                    "spice unreachable (attempt {attempt}/{max_attempts}), retrying in {delay:?}: {err:#}"
+                );
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+            Err(err) => tracing::error!("spice unreachable after {max_attempts} attempts: {err:#}"),
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int64Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow_flight::encode::FlightDataEncoderBuilder;
+    use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+    use arrow_flight::{
+        Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint,
+        FlightInfo, HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaResult, Ticket,
+    };
+    use futures::stream::{self, BoxStream};
+    use futures::StreamExt;
+    use std::sync::Arc;
+    use std::time::Instant;
+    use tokio::net::TcpListener;
+    use tonic::transport::server::TcpIncoming;
+    use tonic::transport::Server;
+    use tonic::{Request, Response, Status, Streaming};
+
+    const LATENCY: Duration = Duration::from_millis(200);
+
+    /// Flight endpoint that takes [`LATENCY`] to plan each query and answers with one row
+    struct SlowFlight;
+
+    type FlightStream<T> = BoxStream<'static, Result<T, Status>>;
+
+    #[tonic::async_trait]
+    impl FlightService for SlowFlight {
+        type HandshakeStream = FlightStream<HandshakeResponse>;
+        type ListFlightsStream = FlightStream<FlightInfo>;
+        type DoGetStream = FlightStream<FlightData>;
+        type DoPutStream = FlightStream<PutResult>;
+        type DoExchangeStream = FlightStream<FlightData>;
+        type DoActionStream = FlightStream<arrow_flight::Result>;
+        type ListActionsStream = FlightStream<ActionType>;
+
+        async fn get_flight_info(
+            &self,
+            _request: Request<FlightDescriptor>,
+        ) -> Result<Response<FlightInfo>, Status> {
+            tokio::time::sleep(LATENCY).await;
+            let endpoint = FlightEndpoint::new().with_ticket(Ticket::new("query"));
+            Ok(Response::new(FlightInfo::new().with_endpoint(endpoint)))
+        }
+
+        async fn do_get(
+            &self,
+            _request: Request<Ticket>,
+        ) -> Result<Response<Self::DoGetStream>, Status> {
+            let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int64, false)]));
+            let batch = RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(vec![1]))])
+                .map_err(|err| Status::internal(err.to_string()))?;
+            let data = FlightDataEncoderBuilder::new()
+                .build(stream::iter([Ok(batch)]))
+                .map_err(Status::from);
+            Ok(Response::new(data.boxed()))
+        }
+
+        async fn handshake(
+            &self,
+            _request: Request<Streaming<HandshakeRequest>>,
+        ) -> Result<Response<Self::HandshakeStream>, Status> {
+            Err(Status::unimplemented("handshake"))
+        }
+
+        async fn list_flights(
+            &self,
+            _request: Request<Criteria>,
+        ) -> Result<Response<Self::ListFlightsStream>, Status> {
+            Err(Status::unimplemented("list_flights"))
+        }
+
+        async fn poll_flight_info(
+            &self,
+            _request: Request<FlightDescriptor>,
+        ) -> Result<Response<PollInfo>, Status> {
+            Err(Status::unimplemented("poll_flight_info"))
+        }
+
+        async fn get_schema(
+            &self,
+            _request: Request<FlightDescriptor>,
+        ) -> Result<Response<SchemaResult>, Status> {
+            Err(Status::unimplemented("get_schema"))
+        }
+
+        async fn do_put(
+            &self,
+            _request: Request<Streaming<FlightData>>,
+        ) -> Result<Response<Self::DoPutStream>, Status> {
+            Err(Status::unimplemented("do_put"))
+        }
+
+        async fn do_exchange(
+            &self,
+            _request: Request<Streaming<FlightData>>,
+        ) -> Result<Response<Self::DoExchangeStream>, Status> {
+            Err(Status::unimplemented("do_exchange"))
+        }
+
+        async fn do_action(
+            &self,
+            _request: Request<Action>,
+        ) -> Result<Response<Self::DoActionStream>, Status> {
+            Err(Status::unimplemented("do_action"))
+        }
+
+        async fn list_actions(
+            &self,
+            _request: Request<Empty>,
+        ) -> Result<Response<Self::ListActionsStream>, Status> {
+            Err(Status::unimplemented("list_actions"))
+        }
+    }
+
+    /// Starts a [`SlowFlight`] server and returns its URL
+    async fn slow_flight() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_service(FlightServiceServer::new(SlowFlight))
+                .serve_with_incoming(incoming),
+        );
+        format!("http://{addr}")
+    }
+
+    /// Time taken to run `count` queries at once
+    async fn run_concurrently(pool: &SpicePool, count: usize) -> Duration {
+        let start = Instant::now();
+        let queries = (0..count).map(|_| probe(pool, "SELECT 1"));
+        for result in futures::future::join_all(queries).await {
+            result.unwrap();
+        }
+        start.elapsed()
+    }
+
+    #[tokio::test]
+    async fn concurrent_queries_run_in_parallel() {
+        let pool = pool(&slow_flight().await, 4).unwrap();
+        let elapsed = run_concurrently(&pool, 4).await;
+        assert!(elapsed < LATENCY * 2, "4 queries took {elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn queries_queue_for_a_free_client() {
+        let pool = pool(&slow_flight().await, 1).unwrap();
+        let elapsed = run_concurrently(&pool, 4).await;
+        assert!(elapsed >= LATENCY * 4, "4 queries took {elapsed:?}");
+    }
+}
@@ -0,0 +1,88 @@
+use crate::Ctx;
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::time::{Duration, Instant};
+
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+const ROW_BUCKETS: &[f64] = &[
+    0.0, 1.0, 10.0, 50.0, 100.0, 500.0, 1000.0, 10000.0, 100000.0,
+];
+
+/// Installs the global Prometheus recorder used by the `metrics` macros
+pub fn install() -> anyhow::Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), LATENCY_BUCKETS)?
+        .set_buckets_for_metric(Matcher::Suffix("_rows".to_string()), ROW_BUCKETS)?
+        .install_recorder()?;
+    Ok(handle)
+}
+
+/// Renders metrics in the Prometheus text format
+pub fn render(handle: &PrometheusHandle, ctx: &Ctx) -> String {
+    let cache = ctx.cache.metrics();
+    metrics::gauge!("query_cache_entries").set(cache.entries as f64);
+    metrics::gauge!("query_cache_rows").set(cache.rows as f64);
+    handle.render()
+}
+
+/// Middleware recording request counts and latencies per route
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    let labels = [
+        ("method", method),
+        ("route", route),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!("http_request_duration_seconds", &labels)
+        .record(start.elapsed().as_secs_f64());
+    response
+}
+
+/// Records the duration and row count of a Spice query
+pub fn record_query(kind: &str, duration: Duration, rows: usize) {
+    let kind = kind.to_string();
+    metrics::histogram!("spice_query_duration_seconds", "kind" => kind.clone())
+        .record(duration.as_secs_f64());
+    metrics::histogram!("spice_query_rows", "kind" => kind).record(rows as f64);
+}
+
+/// Counts a query cache hit or miss
+pub fn record_cache_lookup(hit: bool) {
+    if hit {
+        metrics::counter!("query_cache_hits_total").increment(1);
+    } else {
+        metrics::counter!("query_cache_misses_total").increment(1);
+    }
+}
+
+/// Records the total duration of a streamed chat completion
+pub fn record_chat(model: &str, duration: Duration) {
+    metrics::histogram!("chat_duration_seconds", "model" => model.to_string())
+        .record(duration.as_secs_f64());
+}
+
+/// Counts LLM token usage reported in the final chunk of a chat stream
+pub fn record_chat_tokens(model: &str, prompt_tokens: u32, completion_tokens: u32) {
+    let model = model.to_string();
+    metrics::counter!("llm_prompt_tokens_total", "model" => model.clone())
+        .increment(prompt_tokens.into());
+    metrics::counter!("llm_completion_tokens_total", "model" => model)
+        .increment(completion_tokens.into());
+}
@@ -0,0 +1,37 @@
+use crate::{cache::CacheMetrics, extract::Query, Ctx, Result};
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+/// Query cache hit/miss counts and size
+#[utoipa::path(
+    get,
+    path = "/v1/admin/cache",
+    responses((status = 200, description = "Cache metrics", body = CacheMetrics))
+)]
+pub(super) async fn cache_metrics(State(ctx): State<Ctx>) -> Json<CacheMetrics> {
+    Json(ctx.cache.metrics())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct InvalidateCacheParams {
+    /// Only invalidate results from this season's datasets
+    year: Option<u16>,
+}
+
+/// Invalidate cached query results
+#[utoipa::path(
+    delete,
+    path = "/v1/admin/cache",
+    params(InvalidateCacheParams),
+    responses((status = 204, description = "Cache invalidated"))
+)]
+pub(super) async fn invalidate_cache(
+    State(ctx): State<Ctx>,
+    params: Query<InvalidateCacheParams>,
+) -> Result<StatusCode> {
+    tracing::info!("Invalidating query cache {:?}", params);
+    ctx.cache.invalidate(params.year)?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,188 @@
+use super::{query_spice, season, JsonRecords, QueryKind, Weeks};
+use crate::{
+    error::Error,
+    extract::Json,
+    openai::{system_message, user_message},
+    serde_utils, telemetry, Ctx, Result,
+};
+use async_openai::types::{
+    CreateChatCompletionRequestArgs, ResponseFormat, ResponseFormatJsonSchema,
+};
+use axum::extract::State;
+use fff::pbp::{PbpQuery, STAT_COLUMNS};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Instant;
+use utoipa::ToSchema;
+
+/// Most rows returned for an interpreted question
+const MAX_ASK_ROWS: u16 = 100;
+
+/// A stats question, or a previously interpreted (and possibly edited) query to re-run
+#[derive(Debug, Deserialize, ToSchema)]
+pub(super) struct AskRequest {
+    /// e.g. "Who led RBs in rushing TDs weeks 4-8 of 2023?"
+    question: Option<String>,
+    query: Option<StatsQuery>,
+}
+
+/// Structured stats query the model fills in from a question
+///
+/// Every field is required (but nullable) so the schema works with strict structured outputs.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(super) struct StatsQuery {
+    year: u16,
+    /// Week number or range (e.g. 3 or 3-5)
+    #[serde(with = "serde_utils::string_opt", default)]
+    #[schema(value_type = Option<String>)]
+    weeks: Option<Weeks>,
+    player: Option<String>,
+    team: Option<String>,
+    position: Option<String>,
+    /// Sum stats per player instead of returning per-game rows
+    #[serde(default)]
+    aggregate: bool,
+    /// Stat column to rank season totals by, implies `aggregate`
+    sort: Option<String>,
+    limit: Option<u16>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct AskResponse {
+    /// How the question was interpreted, can be edited and sent back as `query`
+    query: StatsQuery,
+    #[schema(value_type = Vec<Object>)]
+    records: JsonRecords,
+}
+
+impl StatsQuery {
+    /// JSON schema for structured outputs, matching the serde representation above
+    fn schema() -> serde_json::Value {
+        let nullable =
+            |ty: &str, description: &str| json!({"type": [ty, "null"], "description": description});
+        json!({
+            "type": "object",
+            "properties": {
+                "year": {"type": "integer", "description": "Season"},
+                "weeks": nullable("string", "Week number or range, e.g. \"3\" or \"4-8\""),
+                "player": nullable("string", "Full or partial player name"),
+                "team": nullable("string", "Team abbreviation, e.g. KC"),
+                "position": nullable("string", "Roster position, e.g. QB, RB, WR or TE"),
+                "aggregate": {"type": "boolean", "description": "Season totals instead of per-game rows"},
+                "sort": {
+                    "type": ["string", "null"],
+                    "enum": STAT_COLUMNS.iter().map(|stat| Some(*stat)).chain([None]).collect::<Vec<_>>(),
+                    "description": "Stat to rank players by, for questions about leaders",
+                },
+                "limit": nullable("integer", "Number of rows, e.g. 1 for \"who led\""),
+            },
+            "required": ["year", "weeks", "player", "team", "position", "aggregate", "sort", "limit"],
+            "additionalProperties": false,
+        })
+    }
+
+    fn make_query(&self, ctx: &Ctx) -> Result<String> {
+        tracing::trace!("StatsQuery {:?}", self);
+        let mut query_builder = PbpQuery::year(season(ctx.config.years, Some(self.year))?)
+            .join_roster()
+            .limit(self.limit.unwrap_or(MAX_ASK_ROWS).min(MAX_ASK_ROWS));
+
+        if let Some(player_name) = &self.player {
+            query_builder = query_builder.filter_player(player_name);
+        }
+
+        if let Some(team) = &self.team {
+            query_builder = query_builder.filter_team(team);
+        }
+
+        if let Some(position) = &self.position {
+            query_builder = query_builder.filter_position(position);
+        }
+
+        if let Some(weeks) = self.weeks {
+            query_builder = weeks.filter(query_builder);
+        }
+
+        match &self.sort {
+            Some(stat) => query_builder = query_builder.rank_by(stat)?,
+            None if self.aggregate => query_builder = query_builder.aggregate(),
+            None => {}
+        }
+
+        Ok(query_builder.sql())
+    }
+}
+
+/// Answer a stats question with a table
+///
+/// The question is interpreted into a structured query by the model, which is returned
+/// alongside the results. Send `query` instead of `question` to re-run an edited query.
+#[utoipa::path(
+    post,
+    path = "/v1/ask",
+    request_body = AskRequest,
+    responses(
+        (status = 200, description = "Interpreted query and results", body = AskResponse),
+        (status = 400, description = "Missing question or invalid query"),
+        (status = 502, description = "Model is unreachable or couldn't interpret the question"),
+    )
+)]
+pub(super) async fn ask(
+    State(ctx): State<Ctx>,
+    Json(req): Json<AskRequest>,
+) -> Result<Json<AskResponse>> {
+    let query = match (req.query, req.question) {
+        (Some(query), _) => query,
+        (None, Some(question)) => interpret(&ctx, &question).await?,
+        (None, None) => return Err(Error::bad_req("either `question` or `query` is required")),
+    };
+    let sql = query.make_query(&ctx)?;
+    let records = query_spice(&ctx, QueryKind::Ask, &sql).await?;
+    Ok(Json(AskResponse { query, records }))
+}
+
+/// Has the model translate a question into a [`StatsQuery`] with structured outputs
+async fn interpret(ctx: &Ctx, question: &str) -> Result<StatsQuery> {
+    let years = ctx.config.years;
+    let instructions = format!(
+        "Translate NFL fantasy football stats questions into a query. \
+        Seasons {years} are available, default to {latest}. \
+        Weeks 1-18 are the regular season and 19-22 the postseason. \
+        Use `sort` with one of the listed stats for questions about leaders, and `aggregate` \
+        for season or multi-week totals.",
+        latest = years.latest(),
+    );
+    let model = &ctx.config.ask_model;
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages([system_message(&instructions), user_message(question)])
+        .response_format(ResponseFormat::JsonSchema {
+            json_schema: ResponseFormatJsonSchema {
+                description: Some("Filters and sorting for a player stats query".to_string()),
+                name: "stats_query".to_string(),
+                schema: Some(StatsQuery::schema()),
+                strict: Some(true),
+            },
+        })
+        .build()?;
+
+    let start = Instant::now();
+    let response = ctx.openai.inner().chat().create(request).await?;
+    telemetry::record_chat(model, start.elapsed());
+    if let Some(usage) = &response.usage {
+        telemetry::record_chat_tokens(model, usage.prompt_tokens, usage.completion_tokens);
+    }
+
+    let content = response
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+        .ok_or_else(|| Error::BadGateway {
+            message: "model returned no query".into(),
+        })?;
+    tracing::info!("interpreted {question:?} as {content}");
+    serde_json::from_str(&content).map_err(|err| Error::BadGateway {
+        message: format!("model returned an invalid query: {err}").into(),
+    })
+}
@@ -0,0 +1,212 @@
+use crate::{
+    auth::ApiKey,
+    conversations::{Conversation, Message, Role},
+    error::Error,
+    extract::{Json, Query},
+    Ctx, Result,
+};
+use async_openai::types::{ChatCompletionRequestMessage, ChatCompletionRequestUserMessageContent};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct ListConversationsParams {
+    /// Number of conversations to return (default 50)
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub(super) struct CreateConversation {
+    title: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub(super) struct UpdateConversation {
+    title: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub(super) struct NewMessage {
+    role: Role,
+    content: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct ConversationDetail {
+    #[serde(flatten)]
+    conversation: Conversation,
+    messages: Vec<Message>,
+}
+
+/// Conversations, most recently updated first
+#[utoipa::path(
+    get,
+    path = "/v1/conversations",
+    params(ListConversationsParams),
+    responses((status = 200, description = "Conversations", body = Vec<Conversation>))
+)]
+pub(super) async fn list_conversations(
+    State(ctx): State<Ctx>,
+    key: Option<Extension<Arc<ApiKey>>>,
+    params: Query<ListConversationsParams>,
+) -> Result<Json<Vec<Conversation>>> {
+    let limit = params.limit.unwrap_or(50).min(500);
+    let conversations = ctx
+        .conversations
+        .list(owner(key), limit, params.offset.unwrap_or(0))
+        .await?;
+    Ok(Json(conversations))
+}
+
+/// Start a conversation, to be passed as `conversation` to chat completions
+#[utoipa::path(
+    post,
+    path = "/v1/conversations",
+    request_body = CreateConversation,
+    responses((status = 201, description = "Created conversation", body = Conversation))
+)]
+pub(super) async fn create_conversation(
+    State(ctx): State<Ctx>,
+    key: Option<Extension<Arc<ApiKey>>>,
+    Json(req): Json<CreateConversation>,
+) -> Result<(StatusCode, Json<Conversation>)> {
+    let title = req.title.unwrap_or_else(|| "Untitled".to_string());
+    let conversation = ctx.conversations.create(title, owner(key)).await?;
+    Ok((StatusCode::CREATED, Json(conversation)))
+}
+
+/// A conversation with its messages
+#[utoipa::path(
+    get,
+    path = "/v1/conversations/{id}",
+    params(("id" = Uuid, Path, description = "Conversation id")),
+    responses(
+        (status = 200, description = "Conversation and messages", body = ConversationDetail),
+        (status = 404, description = "Conversation not found"),
+    )
+)]
+pub(super) async fn get_conversation(
+    State(ctx): State<Ctx>,
+    key: Option<Extension<Arc<ApiKey>>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ConversationDetail>> {
+    let conversation = ctx
+        .conversations
+        .get(id, owner(key))
+        .await?
+        .ok_or(Error::NotFound)?;
+    let messages = ctx.conversations.messages(id).await?;
+    Ok(Json(ConversationDetail {
+        conversation,
+        messages,
+    }))
+}
+
+/// Rename a conversation
+#[utoipa::path(
+    patch,
+    path = "/v1/conversations/{id}",
+    params(("id" = Uuid, Path, description = "Conversation id")),
+    request_body = UpdateConversation,
+    responses(
+        (status = 200, description = "Updated conversation", body = Conversation),
+        (status = 404, description = "Conversation not found"),
+    )
+)]
+pub(super) async fn update_conversation(
+    State(ctx): State<Ctx>,
+    key: Option<Extension<Arc<ApiKey>>>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateConversation>,
+) -> Result<Json<Conversation>> {
+    let owner = owner(key);
+    let renamed = ctx.conversations.rename(id, owner.clone(), req.title);
+    if !renamed.await? {
+        return Err(Error::NotFound);
+    }
+    let conversation = ctx
+        .conversations
+        .get(id, owner)
+        .await?
+        .ok_or(Error::NotFound)?;
+    Ok(Json(conversation))
+}
+
+/// Delete a conversation and its messages
+#[utoipa::path(
+    delete,
+    path = "/v1/conversations/{id}",
+    params(("id" = Uuid, Path, description = "Conversation id")),
+    responses(
+        (status = 204, description = "Conversation deleted"),
+        (status = 404, description = "Conversation not found"),
+    )
+)]
+pub(super) async fn delete_conversation(
+    State(ctx): State<Ctx>,
+    key: Option<Extension<Arc<ApiKey>>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode> {
+    if ctx.conversations.delete(id, owner(key)).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound)
+    }
+}
+
+/// Append messages to a conversation
+///
+/// Chat completions with a `conversation` param append the user's latest message and the
+/// assistant's answer automatically, so this is only needed for messages exchanged elsewhere.
+#[utoipa::path(
+    post,
+    path = "/v1/conversations/{id}/messages",
+    params(("id" = Uuid, Path, description = "Conversation id")),
+    request_body = Vec<NewMessage>,
+    responses(
+        (status = 204, description = "Messages appended"),
+        (status = 404, description = "Conversation not found"),
+    )
+)]
+pub(super) async fn append_messages(
+    State(ctx): State<Ctx>,
+    key: Option<Extension<Arc<ApiKey>>>,
+    Path(id): Path<Uuid>,
+    Json(messages): Json<Vec<NewMessage>>,
+) -> Result<StatusCode> {
+    let messages = messages
+        .into_iter()
+        .map(|msg| (msg.role, msg.content))
+        .collect();
+    if ctx.conversations.append(id, owner(key), messages).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(Error::NotFound)
+    }
+}
+
+/// Name of the API key making the request, which owns the conversations it creates
+pub(super) fn owner(key: Option<Extension<Arc<ApiKey>>>) -> Option<String> {
+    key.map(|Extension(key)| key.name.clone())
+}
+
+/// Text of the last message in a chat request, if it's from the user
+pub(super) fn latest_user_message(messages: &[ChatCompletionRequestMessage]) -> Option<String> {
+    let ChatCompletionRequestMessage::User(msg) = messages.last()? else {
+        return None;
+    };
+    match &msg.content {
+        ChatCompletionRequestUserMessageContent::Text(text) => Some(text.clone()),
+        // Images and other content parts aren't stored
+        ChatCompletionRequestUserMessageContent::Array(_) => None,
+    }
+}
@@ -0,0 +1,176 @@
+use crate::spice::{self, SpicePool};
+use crate::Result;
+use arrow::array::RecordBatch;
+use arrow::error::ArrowError;
+use arrow::ipc::writer::StreamWriter;
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use futures::{Stream, TryStreamExt};
+
+/// Result formats that can be requested with an `Accept` header
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(super) enum ResultFormat {
+    Json,
+    ArrowStream,
+    Csv,
+    Ndjson,
+}
+
+impl ResultFormat {
+    /// Picks the first supported format from the `Accept` header, defaulting to JSON
+    pub(super) fn from_headers(headers: &HeaderMap) -> ResultFormat {
+        let accept = headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|mime| mime.split(';').next().unwrap_or_default().trim());
+
+        for mime in accept {
+            match mime {
+                "application/vnd.apache.arrow.stream" => return ResultFormat::ArrowStream,
+                "text/csv" => return ResultFormat::Csv,
+                "application/x-ndjson" => return ResultFormat::Ndjson,
+                "application/json" => return ResultFormat::Json,
+                _ => {}
+            }
+        }
+        ResultFormat::Json
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            ResultFormat::Json => "application/json",
+            ResultFormat::ArrowStream => "application/vnd.apache.arrow.stream",
+            ResultFormat::Csv => "text/csv",
+            ResultFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+/// Encodes record batches incrementally so each batch can be sent as soon as it arrives
+enum BatchEncoder {
+    ArrowStream(Option<StreamWriter<Vec<u8>>>),
+    Csv { header: bool },
+    Ndjson,
+}
+
+impl BatchEncoder {
+    fn new(format: ResultFormat) -> BatchEncoder {
+        match format {
+            ResultFormat::ArrowStream => BatchEncoder::ArrowStream(None),
+            ResultFormat::Csv => BatchEncoder::Csv { header: true },
+            ResultFormat::Ndjson | ResultFormat::Json => BatchEncoder::Ndjson,
+        }
+    }
+
+    fn encode(&mut self, batch: &RecordBatch) -> Result<Vec<u8>, ArrowError> {
+        match self {
+            BatchEncoder::ArrowStream(writer) => {
+                if writer.is_none() {
+                    *writer = Some(StreamWriter::try_new(Vec::new(), &batch.schema())?);
+                }
+                let writer = writer.as_mut().expect("initialized writer");
+                writer.write(batch)?;
+                Ok(std::mem::take(writer.get_mut()))
+            }
+            BatchEncoder::Csv { header } => {
+                let mut writer = arrow::csv::WriterBuilder::new()
+                    .with_header(*header)
+                    .build(Vec::new());
+                writer.write(batch)?;
+                *header = false;
+                Ok(writer.into_inner())
+            }
+            BatchEncoder::Ndjson => {
+                let mut writer = arrow::json::LineDelimitedWriter::new(Vec::new());
+                writer.write(batch)?;
+                writer.finish()?;
+                Ok(writer.into_inner())
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Result<Vec<u8>, ArrowError> {
+        match self {
+            BatchEncoder::ArrowStream(Some(writer)) => {
+                writer.finish()?;
+                Ok(std::mem::take(writer.get_mut()))
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Runs a query and streams the encoded record batches to the client as they arrive from Spice
+pub(super) async fn stream_spice(
+    spice: &SpicePool,
+    query: &str,
+    format: ResultFormat,
+) -> Result<Response> {
+    tracing::info!("streaming query as {:?}: {query}", format);
+
+    let flight = spice::query(spice, query).await?;
+
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(format.content_type()),
+        )],
+        Body::from_stream(encode_stream(flight, format)),
+    )
+        .into_response())
+}
+
+fn encode_stream<S>(
+    batches: S,
+    format: ResultFormat,
+) -> impl Stream<Item = anyhow::Result<Vec<u8>>> + Send + 'static
+where
+    S: Stream<Item = anyhow::Result<RecordBatch>> + Send + 'static,
+{
+    let mut encoder = BatchEncoder::new(format);
+    async_stream::try_stream! {
+        let mut batches = std::pin::pin!(batches);
+        while let Some(batch) = batches.try_next().await? {
+            yield encoder.encode(&batch)?;
+        }
+        yield encoder.finish()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::ipc::reader::StreamReader;
+    use std::sync::Arc;
+
+    async fn encode(batches: Vec<RecordBatch>, format: ResultFormat) -> Vec<u8> {
+        let batches = futures::stream::iter(batches.into_iter().map(Ok));
+        let chunks: Vec<_> = encode_stream(batches, format).try_collect().await.unwrap();
+        chunks.concat()
+    }
+
+    #[tokio::test]
+    async fn empty_arrow_stream_has_schema() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "player_name",
+            DataType::Utf8,
+            true,
+        )]));
+        let body = encode(
+            vec![RecordBatch::new_empty(schema.clone())],
+            ResultFormat::ArrowStream,
+        )
+        .await;
+
+        let reader = StreamReader::try_new(body.as_slice(), None).unwrap();
+        assert_eq!(reader.schema(), schema);
+        let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+        assert_eq!(rows, 0);
+    }
+}
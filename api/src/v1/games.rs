@@ -0,0 +1,115 @@
+use super::{query_spice, season, JsonRecords, QueryKind, Weeks};
+use crate::{error::Error, extract::Query, serde_utils, Ctx, Result};
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use fff::{
+    games::{self, GameQuery},
+    pbp::PbpQuery,
+    years::Years,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct ListGamesParams {
+    year: Option<u16>,
+
+    /// Week number or range (e.g. 3 or 3-5), also accepted as `week`
+    #[serde(with = "serde_utils::string_opt", default, alias = "week")]
+    #[param(value_type = Option<String>)]
+    weeks: Option<Weeks>,
+    /// Games where this team is home or away
+    team: Option<String>,
+}
+
+impl ListGamesParams {
+    fn make_query(&self, years: Years) -> Result<String> {
+        tracing::trace!("ListGamesParams {:?}", self);
+        let mut query = GameQuery::new(season(years, self.year)?);
+
+        if let Some(team) = &self.team {
+            query = query.team(team);
+        }
+
+        if let Some(weeks) = self.weeks {
+            query = weeks.filter(query);
+        }
+
+        Ok(query.sql())
+    }
+}
+
+/// Games with teams and final scores
+#[utoipa::path(
+    get,
+    path = "/v1/games",
+    params(ListGamesParams),
+    responses(
+        (status = 200, description = "Games", body = Vec<Object>),
+        (status = 400, description = "Invalid query params"),
+    )
+)]
+pub(super) async fn list_games(
+    State(ctx): State<Ctx>,
+    params: Query<ListGamesParams>,
+) -> Result<Json<JsonRecords>> {
+    let query = params.make_query(ctx.config.years)?;
+    query_spice(&ctx, QueryKind::Games, &query).await.map(Json)
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct BoxScore {
+    /// Teams and final score
+    #[schema(value_type = Object)]
+    game: Map<String, Value>,
+    /// Stat lines for players on both teams
+    #[schema(value_type = Vec<Object>)]
+    players: JsonRecords,
+    #[schema(value_type = Vec<Object>)]
+    scoring_plays: JsonRecords,
+}
+
+/// Box score with player stat lines, scoring plays and final score
+#[utoipa::path(
+    get,
+    path = "/v1/games/{game_id}",
+    params(("game_id" = String, Path, description = "nflverse game id (e.g. 2024_01_BAL_KC)")),
+    responses(
+        (status = 200, description = "Box score", body = BoxScore),
+        (status = 404, description = "Game not found"),
+    )
+)]
+pub(super) async fn get_game(
+    State(ctx): State<Ctx>,
+    Path(game_id): Path<String>,
+) -> Result<Json<BoxScore>> {
+    let year = games::season_from_game_id(&game_id)
+        .filter(|year| ctx.config.years.contains(*year))
+        .ok_or(Error::NotFound)?;
+
+    let query = GameQuery::new(year).game(&game_id).sql();
+    let game = query_spice(&ctx, QueryKind::Games, &query)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or(Error::NotFound)?;
+
+    let query = PbpQuery::year(year)
+        .filter_game(&game_id)
+        .join_roster()
+        .sql();
+    let players = query_spice(&ctx, QueryKind::Games, &query).await?;
+
+    let query = GameQuery::scoring_plays(year, &game_id).sql();
+    let scoring_plays = query_spice(&ctx, QueryKind::Games, &query).await?;
+
+    Ok(Json(BoxScore {
+        game,
+        players,
+        scoring_plays,
+    }))
+}
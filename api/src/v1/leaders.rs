@@ -0,0 +1,75 @@
+use super::{query_spice, season, JsonRecords, QueryKind, Weeks};
+use crate::{extract::Query, serde_utils, Ctx, Result};
+use axum::{extract::State, Json};
+use fff::{pbp::PbpQuery, scoring::Scoring, years::Years};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct GetLeadersParams {
+    /// Stat column to rank by (e.g. receiving_yards)
+    stat: String,
+    year: Option<u16>,
+    position: Option<String>,
+
+    /// Week number or range (e.g. 3 or 3-5)
+    #[serde(with = "serde_utils::string_opt", default)]
+    #[param(value_type = Option<String>)]
+    weeks: Option<Weeks>,
+    team: Option<String>,
+    /// Number of players to return (default 24)
+    pub(super) n: Option<u16>,
+    /// Adds `fantasy_points` under a named scoring config (e.g. ppr)
+    pub(super) scoring: Option<String>,
+}
+
+impl GetLeadersParams {
+    pub(super) fn make_query(&self, years: Years, scoring: Option<&Scoring>) -> Result<String> {
+        tracing::trace!("GetLeadersParams {:?}", self);
+        let mut query_builder = PbpQuery::year(season(years, self.year)?);
+
+        if let Some(team) = &self.team {
+            query_builder = query_builder.filter_team(team);
+        }
+
+        if let Some(position) = &self.position {
+            query_builder = query_builder.filter_position(position);
+        }
+
+        if let Some(weeks) = self.weeks {
+            query_builder = weeks.filter(query_builder);
+        }
+
+        if let Some(scoring) = scoring {
+            query_builder = query_builder.fantasy_points(scoring);
+        }
+
+        let query = query_builder
+            .rank_by(&self.stat)?
+            .limit(self.n.unwrap_or(24))
+            .sql();
+        Ok(query)
+    }
+}
+
+/// Players ranked by season totals of a stat, with overall and positional rank
+#[utoipa::path(
+    get,
+    path = "/v1/leaders",
+    params(GetLeadersParams),
+    responses(
+        (status = 200, description = "Ranked season totals", body = Vec<Object>),
+        (status = 400, description = "Invalid query params or unknown stat"),
+    )
+)]
+pub(super) async fn get_leaders(
+    State(ctx): State<Ctx>,
+    params: Query<GetLeadersParams>,
+) -> Result<Json<JsonRecords>> {
+    let scoring = ctx.scoring.resolve(params.scoring.as_deref())?;
+    let query = params.make_query(ctx.config.years, scoring.as_deref())?;
+    query_spice(&ctx, QueryKind::Leaders, &query)
+        .await
+        .map(Json)
+}
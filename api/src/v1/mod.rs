@@ -1,33 +1,141 @@
-use crate::{serde_utils, Ctx, Result};
-use anyhow::{anyhow, Context};
+use crate::{
+    auth::ApiKey,
+    conversations::Role,
+    error::Error,
+    extract::{Json, Query},
+    openai::{system_message, OpenAiClient},
+    serde_utils, spice, telemetry, Ctx, Result,
+};
+use anyhow::Context;
 use arrow::{array::RecordBatch, json::ArrayWriter};
 use async_openai::error::OpenAIError;
-use async_openai::types::{ChatCompletionResponseStream, CreateChatCompletionRequest};
+use async_openai::types::{
+    ChatCompletionResponseStream, ChatCompletionStreamOptions, CreateChatCompletionRequest,
+};
 use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::{
-    extract::{Query, State},
+    extract::State,
+    http::HeaderMap,
     response::{IntoResponse, Response},
     routing::{get, post},
-    Json, Router,
+    Extension, Router,
 };
-use fff::{pbp::PbpQuery, rosters::RosterQuery};
+use export::ResultFormat;
+use fff::{pbp::PbpQuery, rosters::RosterQuery, scoring::Scoring, weeks::WeekFilter, years::Years};
 use futures::{StreamExt, TryStreamExt};
 use itertools::Itertools;
-use serde::Deserialize;
+use parse_display::Display;
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
-use std::time::Duration;
-use tokio::sync::Mutex;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{Instrument, Span};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+mod admin;
+mod ask;
+mod conversations;
+mod export;
+mod games;
+mod leaders;
+mod openapi;
+mod players;
+mod plays;
+mod prompt;
+mod rankings;
+pub(crate) mod schema;
+mod teams;
+mod tools;
 
-type JsonRecords = Vec<Map<String, Value>>;
+pub(crate) type JsonRecords = Vec<Map<String, Value>>;
 
 pub(crate) fn router() -> Router<Ctx> {
     Router::new()
+        .route("/v1/years", get(list_years))
+        .route("/v1/scoring", get(list_scoring))
         .route("/v1/stats", get(get_stats))
+        .route("/v1/rankings", get(rankings::get_rankings))
         .route("/v1/players", get(search_players))
+        .route("/v1/players/:player_id", get(players::get_player))
+        .route("/v1/leaders", get(leaders::get_leaders))
+        .route("/v1/teams/:team", get(teams::get_team))
+        .route("/v1/games", get(games::list_games))
+        .route("/v1/games/:game_id", get(games::get_game))
+        .route("/v1/plays", get(plays::list_plays))
         .route("/v1/chat/completions", post(stream_chat))
+        .route("/v1/ask", post(ask::ask))
+        .route(
+            "/v1/conversations",
+            get(conversations::list_conversations).post(conversations::create_conversation),
+        )
+        .route(
+            "/v1/conversations/:id",
+            get(conversations::get_conversation)
+                .patch(conversations::update_conversation)
+                .delete(conversations::delete_conversation),
+        )
+        .route(
+            "/v1/conversations/:id/messages",
+            post(conversations::append_messages),
+        )
+        .route("/v1/openapi.json", get(openapi::openapi_json))
+        .route(
+            "/v1/admin/cache",
+            get(admin::cache_metrics).delete(admin::invalidate_cache),
+        )
+}
+
+/// Resolves a `year` param to a configured season, defaulting to the latest
+fn season(years: Years, year: Option<u16>) -> Result<u16> {
+    match year {
+        None => Ok(years.latest()),
+        Some(year) if years.contains(year) => Ok(year),
+        Some(year) => Err(Error::bad_req(format!(
+            "no data for {year}, seasons available are {years}"
+        ))),
+    }
+}
+
+/// Configured seasons, latest first
+#[utoipa::path(
+    get,
+    path = "/v1/years",
+    responses((status = 200, description = "Seasons with data", body = Vec<u16>))
+)]
+async fn list_years(State(ctx): State<Ctx>) -> Json<Vec<u16>> {
+    Json(ctx.config.years.iter().rev().collect())
+}
+
+/// A scoring config usable with `scoring=`
+#[derive(Serialize, ToSchema)]
+struct ScoringConfig {
+    name: String,
+    /// Points per stat, position overrides and bonuses
+    description: String,
+}
+
+/// Named scoring configs, the presets plus any from `SCORING_DIR`
+#[utoipa::path(
+    get,
+    path = "/v1/scoring",
+    responses((status = 200, description = "Scoring configs", body = Vec<ScoringConfig>))
+)]
+async fn list_scoring(State(ctx): State<Ctx>) -> Json<Vec<ScoringConfig>> {
+    let configs = ctx
+        .scoring
+        .iter()
+        .map(|(name, scoring)| ScoringConfig {
+            name: name.to_string(),
+            description: scoring.to_string(),
+        })
+        .collect();
+    Json(configs)
 }
 
+/// Last week of the postseason
+const MAX_WEEK: u16 = 22;
+
 #[derive(Copy, Clone, Debug)]
 enum Weeks {
     Week(u16),
@@ -35,59 +143,117 @@ enum Weeks {
 }
 
 impl std::str::FromStr for Weeks {
-    type Err = std::num::ParseIntError;
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<u16> = s.split('-').map(str::parse).try_collect()?;
+        let invalid = || format!("expected a week (e.g. 3) or week range (e.g. 3-5), got {s:?}");
+        let parts: Vec<u16> = s
+            .split('-')
+            .map(str::parse)
+            .try_collect()
+            .map_err(|_| invalid())?;
+        if parts.iter().any(|week| !(1..=MAX_WEEK).contains(week)) {
+            return Err(format!("weeks must be between 1 and {MAX_WEEK}, got {s:?}"));
+        }
         match &*parts {
             [single] => Ok(Weeks::Week(*single)),
             [start, end] if start <= end => Ok(Weeks::WeekRange(*start, *end)),
-            _ => Err(s.parse::<u16>().unwrap_err()),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for Weeks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Weeks::Week(week) => write!(f, "{week}"),
+            Weeks::WeekRange(start, end) => write!(f, "{start}-{end}"),
+        }
+    }
+}
+
+impl Weeks {
+    /// Applies this week or week range as a filter on a query
+    fn filter<Q: WeekFilter>(self, query: Q) -> Q {
+        match self {
+            Weeks::Week(week) => query.single_week(week),
+            Weeks::WeekRange(start, end) => query.week_range(start, end),
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct SearchPlayersParams {
+    /// Case-insensitive substring of the player's name
     search: String,
     year: Option<u16>,
+    /// Roster week, defaults to the player's latest week
     week: Option<u16>,
 }
 
 impl SearchPlayersParams {
-    fn make_query(&self) -> String {
-        let year = self.year.unwrap_or(2024);
+    fn make_query(&self, years: Years) -> Result<String> {
+        let year = season(years, self.year)?;
         let query = RosterQuery::new(year)
             .name_search(&self.search)
             .single_week(self.week);
-        query.sql()
+        Ok(query.sql())
     }
 }
 
+/// Search rosters by player name
+#[utoipa::path(
+    get,
+    path = "/v1/players",
+    params(SearchPlayersParams),
+    responses(
+        (status = 200, description = "Matching roster rows", body = Vec<Object>),
+        (status = 400, description = "Invalid query params"),
+    )
+)]
 #[axum::debug_handler]
 async fn search_players(
     State(ctx): State<Ctx>,
     params: Query<SearchPlayersParams>,
 ) -> Result<Json<Vec<Map<String, Value>>>> {
-    let query = params.make_query();
-    query_spice(&ctx.spice, &query).await.map(Json)
+    let query = params.make_query(ctx.config.years)?;
+    query_spice(&ctx, QueryKind::Players, &query)
+        .await
+        .map(Json)
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct GetStatsParams {
     year: Option<u16>,
+    /// Filter by player name
     player: Option<String>,
     position: Option<String>,
 
+    /// Week number or range (e.g. 3 or 3-5)
     #[serde(with = "serde_utils::string_opt", default)]
+    #[param(value_type = Option<String>)]
     weeks: Option<Weeks>,
     team: Option<String>,
+    /// Adds `fantasy_points` under a named scoring config (e.g. ppr)
+    scoring: Option<String>,
 }
 
 impl GetStatsParams {
-    fn make_query(&self) -> String {
+    fn make_query(
+        &self,
+        years: Years,
+        scoring: Option<&Scoring>,
+        format: ResultFormat,
+    ) -> Result<String> {
         tracing::trace!("GetStatsParams {:?}", self);
-        let mut query_builder = PbpQuery::year(self.year.unwrap_or(2024)).join_roster();
+        let mut query_builder = PbpQuery::year(season(years, self.year)?).join_roster();
+
+        // Streamed formats aren't capped like JSON responses
+        if format != ResultFormat::Json {
+            query_builder = query_builder.unlimited();
+        }
 
         if let Some(player_name) = &self.player {
             query_builder = query_builder.filter_player(player_name);
@@ -101,37 +267,79 @@ impl GetStatsParams {
             query_builder = query_builder.filter_position(position);
         }
 
-        match self.weeks {
-            Some(Weeks::Week(week)) => {
-                query_builder = query_builder.filter_week(week);
-            }
-            Some(Weeks::WeekRange(start, end)) => {
-                query_builder = query_builder.filter_week_range(start, end);
-            }
-            None => {}
+        if let Some(weeks) = self.weeks {
+            query_builder = weeks.filter(query_builder);
         }
 
-        
-        query_builder.sql()
+        if let Some(scoring) = scoring {
+            query_builder = query_builder.fantasy_points(scoring);
+        }
+
+        Ok(query_builder.sql())
     }
 }
 
+/// Per-game player stats
+///
+/// Responds with JSON by default (capped at 500 rows). Bulk results can be streamed with an
+/// `Accept` header of `application/vnd.apache.arrow.stream`, `text/csv` or `application/x-ndjson`.
+#[utoipa::path(
+    get,
+    path = "/v1/stats",
+    params(GetStatsParams),
+    responses(
+        (status = 200, description = "Per-game stats", body = Vec<Object>, content_type = [
+            "application/json",
+            "application/vnd.apache.arrow.stream",
+            "text/csv",
+            "application/x-ndjson",
+        ]),
+        (status = 400, description = "Invalid query params"),
+    )
+)]
 async fn get_stats(
     State(ctx): State<Ctx>,
+    headers: HeaderMap,
     params: Query<GetStatsParams>,
-) -> Result<Json<Vec<Map<String, Value>>>> {
-    let query = params.make_query();
-    query_spice(&ctx.spice, &query).await.map(Json)
+) -> Result<Response> {
+    let format = ResultFormat::from_headers(&headers);
+    let scoring = ctx.scoring.resolve(params.scoring.as_deref())?;
+    let query = params.make_query(ctx.config.years, scoring.as_deref(), format)?;
+    match format {
+        ResultFormat::Json => query_spice(&ctx, QueryKind::Stats, &query)
+            .await
+            .map(|records| Json(records).into_response()),
+        _ => export::stream_spice(&ctx.spice, &query, format).await,
+    }
+}
+
+/// Kind of query, used to label query metrics
+#[derive(Copy, Clone, Debug, Display)]
+#[display(style = "snake_case")]
+enum QueryKind {
+    Stats,
+    Players,
+    Leaders,
+    Teams,
+    Games,
+    Plays,
+    Ask,
+    /// Tool calls made by the model during a chat
+    Chat,
+    Rankings,
+    Schema,
 }
 
-async fn query_spice(spice: &Mutex<spiceai::Client>, query: &str) -> Result<JsonRecords> {
+async fn query_spice(ctx: &Ctx, kind: QueryKind, query: &str) -> Result<JsonRecords> {
+    if let Some(records) = ctx.cache.get(query).await {
+        tracing::info!("cached query: {query}");
+        return Ok(records);
+    }
+
     tracing::info!("query: {query}");
+    let start = Instant::now();
 
-    let mut spice = spice.lock().await;
-    let flight = spice
-        .query(query)
-        .await
-        .map_err(|err| anyhow!("Query error: {}", err))?;
+    let flight = spice::query(&ctx.spice, query).await?;
 
     tracing::info!("collecting query results");
     let batches: Vec<_> = flight
@@ -139,67 +347,244 @@ async fn query_spice(spice: &Mutex<spiceai::Client>, query: &str) -> Result<Json
         .await
         .context("collecting query results")?;
 
+    let rows = batches.iter().map(RecordBatch::num_rows).sum();
+    telemetry::record_query(&kind.to_string(), start.elapsed(), rows);
+
     tracing::info!("converting {} record batcheds to json", batches.len());
     let json_resp = record_batches_to_json(batches, 500);
+    ctx.cache.insert(query, &json_resp).await;
     Ok(json_resp)
 }
 
+/// Most rounds of tool calls resolved before giving up on a chat
+const MAX_TOOL_ROUNDS: usize = 5;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ChatParams {
+    /// Conversation to record the latest user message and the answer in
+    conversation: Option<Uuid>,
+}
+
+/// OpenAI-compatible chat completions, streamed as server-sent events
+///
+/// Unless the request brings its own `tools`, the model is offered tools backed by the stats
+/// queries, which are run server-side before the final answer is streamed.
+#[utoipa::path(
+    post,
+    path = "/v1/chat/completions",
+    params(ChatParams),
+    request_body(content = Object, description = "OpenAI chat completion request"),
+    responses(
+        (status = 200, description = "Chat completion chunks, ending with an `error` event if the model fails mid-stream", content_type = "text/event-stream"),
+        (status = 400, description = "Invalid chat request"),
+        (status = 404, description = "Conversation not found"),
+        (status = 502, description = "Model is unreachable or failed"),
+    )
+)]
 async fn stream_chat(
     State(ctx): State<Ctx>,
-    Json(data): Json<CreateChatCompletionRequest>,
-) -> Response {
+    key: Option<Extension<Arc<ApiKey>>>,
+    params: Query<ChatParams>,
+    Json(mut data): Json<CreateChatCompletionRequest>,
+) -> Result<Response> {
+    // The question is only saved along with its answer, so failed chats leave no trace
+    let conversation = match params.conversation {
+        Some(id) => {
+            let owner = conversations::owner(key);
+            if ctx.conversations.get(id, owner.clone()).await?.is_none() {
+                return Err(Error::NotFound);
+            }
+            Some(ChatConversation {
+                id,
+                owner,
+                question: conversations::latest_user_message(&data.messages),
+            })
+        }
+        None => None,
+    };
+
+    // Ask for a final usage chunk so token usage can be recorded
+    data.stream_options
+        .get_or_insert(ChatCompletionStreamOptions {
+            include_usage: true,
+        });
+    let model = data.model.clone();
+
+    // Offer our own tools unless the client brought its own
+    let server_tools = data.tools.is_none();
+    if server_tools {
+        data.tools = Some(tools::definitions()?);
+    }
+    match prompt::system_prompt(&ctx, server_tools).await {
+        Ok(prompt) => data.messages.insert(0, system_message(&prompt)),
+        Err(e) => tracing::warn!("chat without system prompt, building it failed: {e:?}"),
+    }
+
     let span = tracing::span!(target: "task_history", tracing::Level::INFO, "ai_chat", input = %serde_json::to_string(&data).unwrap_or_default());
 
     // Create an async stream for SSE
-    let client = &ctx.openai.inner();
     tracing::trace!("Starting openai chat");
-    let response = client.chat().create_stream(data).await.unwrap();
+    let response = start_chat(&ctx.openai, data.clone())
+        .instrument(span.clone())
+        .await?;
     tracing::trace!("Chat started");
 
-    create_sse_response(response, Duration::from_secs(30), span)
+    let request = server_tools.then_some(data);
+    Ok(create_sse_response(
+        ctx,
+        response,
+        model,
+        request,
+        conversation,
+        Duration::from_secs(30),
+        span,
+    ))
 }
 
+/// Conversation a chat is saved to once it's answered
+struct ChatConversation {
+    id: Uuid,
+    owner: Option<String>,
+    /// The user's latest message, saved together with the answer
+    question: Option<String>,
+}
+
+/// Starts a chat stream and waits for its first chunk
+///
+/// The stream only connects once polled, so this surfaces an unreachable or failing model as an
+/// error response instead of a stream that ends with an `error` event.
+async fn start_chat(
+    openai: &OpenAiClient,
+    request: CreateChatCompletionRequest,
+) -> Result<ChatCompletionResponseStream> {
+    let mut strm = openai.inner().chat().create_stream(request).await?;
+    match strm.next().await {
+        Some(Err(e)) => Err(e.into()),
+        first => Ok(Box::pin(futures::stream::iter(first).chain(strm))),
+    }
+}
+
+/// Streams chat chunks to the client as SSE
+///
+/// When `request` is given, tool calls are intercepted instead of forwarded: they're run with
+/// [`tools::resolve`] and the conversation is continued until the model answers.
+/// The question and captured answer are appended to `conversation`, if any.
 fn create_sse_response(
+    ctx: Ctx,
     mut strm: ChatCompletionResponseStream,
+    model: String,
+    mut request: Option<CreateChatCompletionRequest>,
+    conversation: Option<ChatConversation>,
     keep_alive_interval: Duration,
     span: Span,
 ) -> Response {
+    let start = Instant::now();
     Sse::new(Box::pin(async_stream::stream! {
         let mut chat_output = String::new();
-        while let Some(msg) = strm.next().instrument(span.clone()).await {
-            match msg {
-                Ok(resp) => {
-                    if let Some(choice) = resp.choices.first() {
-                        if let Some(intermediate_chat_output) = &choice.delta.content {
-                            chat_output.push_str(intermediate_chat_output);
+        'rounds: for round in 1.. {
+            let mut tool_calls = Vec::new();
+            while let Some(msg) = strm.next().instrument(span.clone()).await {
+                match msg {
+                    Ok(resp) => {
+                        if let Some(usage) = &resp.usage {
+                            telemetry::record_chat_tokens(
+                                &model,
+                                usage.prompt_tokens,
+                                usage.completion_tokens,
+                            );
+                        }
+                        if let Some(choice) = resp.choices.first() {
+                            if let Some(intermediate_chat_output) = &choice.delta.content {
+                                chat_output.push_str(intermediate_chat_output);
+                            }
+                            if let Some(chunks) = &choice.delta.tool_calls {
+                                if request.is_some() {
+                                    tools::accumulate(&mut tool_calls, chunks);
+                                }
+                            }
                         }
+                        // The client only sees the round that answers
+                        if !tool_calls.is_empty() {
+                            continue;
+                        }
+                        let y = Event::default();
+                        match y.json_data(resp).map_err(axum::Error::new) {
+                            Ok(a) => yield Ok(a),
+                            Err(e) => yield Err(e),
+                        }
+                    },
+                    // TODO: Is this openai or spice ending streams with this error?
+                    Err(OpenAIError::StreamError(e)) if e == "Stream ended" => {
+                        break;
                     }
-                    let y = Event::default();
-                    match y.json_data(resp).map_err(axum::Error::new) {
-                        Ok(a) => yield Ok(a),
-                        Err(e) => yield Err(e),
+                    Err(e) => {
+                        tracing::warn!(parent: &span, "chat stream err: {:?}", e);
+                        yield Ok(error_event(Error::from(e)));
+                        break 'rounds;
                     }
-                },
-                // TODO: Is this openai or spice ending streams with this error?
-                Err(OpenAIError::StreamError(e)) if e == "Stream ended" => {
-                    break;
                 }
+            }
+
+            let Some(request) = request.as_mut().filter(|_| !tool_calls.is_empty()) else {
+                break;
+            };
+            if round == MAX_TOOL_ROUNDS {
+                tracing::warn!(parent: &span, "chat exceeded {MAX_TOOL_ROUNDS} rounds of tool calls");
+                yield Ok(error_event(Error::BadGateway {
+                    message: "model made too many tool calls".into(),
+                }));
+                break;
+            }
+            let next = async {
+                tools::resolve(&ctx, &mut request.messages, tool_calls).await?;
+                ctx.openai.inner().chat().create_stream(request.clone()).await
+            };
+            match next.instrument(span.clone()).await {
+                Ok(next) => strm = next,
                 Err(e) => {
-                    tracing::warn!("chat stream err: {:?}", e);
-                    yield Err(axum::Error::new(e.to_string()));
+                    yield Ok(error_event(Error::from(e)));
                     break;
                 }
             }
-        };
+        }
+        telemetry::record_chat(&model, start.elapsed());
         tracing::info!(target: "task_history", parent: &span, captured_output = %chat_output);
+        if let Some(conversation) = conversation.filter(|_| !chat_output.is_empty()) {
+            let ChatConversation { id, owner, question } = conversation;
+            let messages = question
+                .map(|question| (Role::User, question))
+                .into_iter()
+                .chain([(Role::Assistant, chat_output)])
+                .collect();
+            if let Err(e) = ctx.conversations.append(id, owner, messages).await {
+                tracing::error!(parent: &span, "saving chat output to conversation {id}: {e:?}");
+            }
+        }
         drop(span);
     }))
     .keep_alive(KeepAlive::new().interval(keep_alive_interval))
     .into_response()
 }
 
+/// Terminal SSE event for a chat stream that failed after the response started
+///
+/// Shaped like an OpenAI error body so OpenAI-compatible clients surface it as an API error.
+fn error_event(err: Error) -> Event {
+    let code = err.http_code();
+    let body = serde_json::json!({
+        "error": {
+            "code": code.as_u16(),
+            "message": err.http_message(),
+            "type": code.canonical_reason(),
+        }
+    });
+    Event::default().event("error").data(body.to_string())
+}
+
 fn record_batches_to_json(record_batches: Vec<RecordBatch>, max_records: usize) -> JsonRecords {
-    if record_batches.is_empty() {
+    // The writer outputs nothing at all without rows, not even `[]`
+    if record_batches.iter().all(|batch| batch.num_rows() == 0) {
         return Vec::new();
     }
 
@@ -232,3 +617,89 @@ fn record_batches_to_json(record_batches: Vec<RecordBatch>, max_records: usize)
     let json_rows = serde_json::from_reader(json_data.as_slice()).expect("arrow json writer");
     json_rows
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{header, StatusCode};
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    fn request() -> CreateChatCompletionRequest {
+        serde_json::from_value(json!({
+            "model": "test",
+            "messages": [{"role": "user", "content": "Who led the league in rushing?"}],
+            "stream": true,
+        }))
+        .unwrap()
+    }
+
+    /// Serves an OpenAI-compatible chat endpoint that always answers with `status` and `body`
+    async fn mock_model(
+        status: StatusCode,
+        content_type: &'static str,
+        body: String,
+    ) -> OpenAiClient {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(move || async move { (status, [(header::CONTENT_TYPE, content_type)], body) }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        OpenAiClient::with_base_url(&format!("http://{addr}/v1"))
+    }
+
+    async fn model_error(status: StatusCode) -> OpenAiClient {
+        let error = json!({"error": {"message": "failed", "type": "server_error"}});
+        mock_model(status, "application/json", error.to_string()).await
+    }
+
+    async fn start_status(openai: &OpenAiClient) -> StatusCode {
+        match start_chat(openai, request()).await {
+            Ok(_) => StatusCode::OK,
+            Err(e) => e.http_code(),
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_a_healthy_model() {
+        let chunk = json!({
+            "id": "1",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "test",
+            "choices": [{"index": 0, "delta": {"content": "Derrick Henry"}, "finish_reason": null}],
+        });
+        let body = format!("data: {chunk}\n\ndata: [DONE]\n\n");
+        let openai = mock_model(StatusCode::OK, "text/event-stream", body).await;
+
+        let chunks: Vec<_> = start_chat(&openai, request())
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert!(chunks[0].is_ok());
+    }
+
+    #[tokio::test]
+    async fn unreachable_model_is_bad_gateway() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let openai = OpenAiClient::with_base_url(&format!("http://{addr}/v1"));
+        assert_eq!(start_status(&openai).await, StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn rejected_request_is_bad_request() {
+        let openai = model_error(StatusCode::BAD_REQUEST).await;
+        assert_eq!(start_status(&openai).await, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn failing_model_is_bad_gateway() {
+        let openai = model_error(StatusCode::INTERNAL_SERVER_ERROR).await;
+        assert_eq!(start_status(&openai).await, StatusCode::BAD_GATEWAY);
+    }
+}
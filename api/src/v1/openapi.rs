@@ -0,0 +1,55 @@
+use super::{admin, ask, conversations, games, leaders, players, plays, rankings, teams};
+use axum::Json;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Melange API"),
+    paths(
+        super::list_years,
+        super::list_scoring,
+        super::get_stats,
+        super::search_players,
+        super::stream_chat,
+        ask::ask,
+        conversations::list_conversations,
+        conversations::create_conversation,
+        conversations::get_conversation,
+        conversations::update_conversation,
+        conversations::delete_conversation,
+        conversations::append_messages,
+        players::get_player,
+        leaders::get_leaders,
+        rankings::get_rankings,
+        teams::get_team,
+        games::list_games,
+        games::get_game,
+        plays::list_plays,
+        admin::cache_metrics,
+        admin::invalidate_cache,
+    ),
+    components(schemas(
+        super::ScoringConfig,
+        players::PlayerProfile,
+        teams::TeamSummary,
+        rankings::RankingsResponse,
+        games::BoxScore,
+        plays::PlaysPage,
+        ask::AskRequest,
+        ask::StatsQuery,
+        ask::AskResponse,
+        conversations::CreateConversation,
+        conversations::UpdateConversation,
+        conversations::NewMessage,
+        conversations::ConversationDetail,
+        crate::conversations::Conversation,
+        crate::conversations::Message,
+        crate::conversations::Role,
+        crate::cache::CacheMetrics,
+    ))
+)]
+struct ApiDoc;
+
+pub(super) async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
@@ -0,0 +1,81 @@
+use super::{query_spice, JsonRecords, QueryKind};
+use crate::{error::Error, Ctx, Result};
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use fff::{pbp::PbpQuery, rosters::RosterQuery};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct PlayerProfile {
+    /// Latest roster entry for the player
+    #[schema(value_type = Object)]
+    bio: Map<String, Value>,
+    /// Season totals for each configured year the player recorded stats
+    #[schema(value_type = Vec<Object>)]
+    seasons: JsonRecords,
+    /// Per-game stats for the current season
+    #[schema(value_type = Vec<Object>)]
+    games: JsonRecords,
+}
+
+/// Player card with bio, season totals and current season game log
+#[utoipa::path(
+    get,
+    path = "/v1/players/{player_id}",
+    params(("player_id" = String, Path, description = "GSIS player id")),
+    responses(
+        (status = 200, description = "Player profile", body = PlayerProfile),
+        (status = 404, description = "Player not found"),
+    )
+)]
+pub(super) async fn get_player(
+    State(ctx): State<Ctx>,
+    Path(player_id): Path<String>,
+) -> Result<Json<PlayerProfile>> {
+    let years = ctx.config.years;
+
+    let mut bio = None;
+    for year in years.iter().rev() {
+        let query = RosterQuery::new(year)
+            .player_id(&player_id)
+            .single_week(None)
+            .with_age()
+            .sql();
+        if let Some(row) = query_spice(&ctx, QueryKind::Players, &query)
+            .await?
+            .into_iter()
+            .next()
+        {
+            bio = Some(row);
+            break;
+        }
+    }
+    let bio = bio.ok_or(Error::NotFound)?;
+
+    let mut seasons = Vec::new();
+    for year in years.iter() {
+        let query = PbpQuery::year(year)
+            .filter_player_id(&player_id)
+            .aggregate()
+            .sql();
+        for mut row in query_spice(&ctx, QueryKind::Players, &query).await? {
+            row.insert("season".to_string(), year.into());
+            seasons.push(row);
+        }
+    }
+
+    let query = PbpQuery::year(years.latest())
+        .filter_player_id(&player_id)
+        .sql();
+    let games = query_spice(&ctx, QueryKind::Players, &query).await?;
+
+    Ok(Json(PlayerProfile {
+        bio,
+        seasons,
+        games,
+    }))
+}
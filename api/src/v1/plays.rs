@@ -0,0 +1,135 @@
+use super::{query_spice, season, JsonRecords, QueryKind, Weeks};
+use crate::{error::Error, extract::Query, serde_utils, Ctx, Result};
+use axum::{extract::State, Json};
+use fff::{games, plays::PlayQuery, years::Years};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+const MAX_LIMIT: u16 = 500;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct ListPlaysParams {
+    year: Option<u16>,
+    /// Plays involving this player in any role
+    player_id: Option<String>,
+    game_id: Option<String>,
+    team: Option<String>,
+    play_type: Option<String>,
+    down: Option<u8>,
+    min_ydstogo: Option<u16>,
+    max_ydstogo: Option<u16>,
+
+    /// Week number or range (e.g. 3 or 3-5)
+    #[serde(with = "serde_utils::string_opt", default)]
+    #[param(value_type = Option<String>)]
+    weeks: Option<Weeks>,
+
+    /// Page size (default 100, between 1 and 500)
+    limit: Option<u16>,
+    offset: Option<u32>,
+}
+
+impl ListPlaysParams {
+    fn make_query(&self, years: Years) -> Result<String> {
+        tracing::trace!("ListPlaysParams {:?}", self);
+        if self.down.is_some_and(|down| !(1..=4).contains(&down)) {
+            return Err(Error::bad_req("down must be between 1 and 4"));
+        }
+        if let (Some(min), Some(max)) = (self.min_ydstogo, self.max_ydstogo) {
+            if min > max {
+                return Err(Error::bad_req("min_ydstogo must not exceed max_ydstogo"));
+            }
+        }
+        if self.limit == Some(0) {
+            return Err(Error::bad_req("limit must be at least 1"));
+        }
+
+        let game_year = self.game_id.as_deref().and_then(games::season_from_game_id);
+        let mut query = PlayQuery::new(season(years, self.year.or(game_year))?);
+
+        if let Some(player_id) = &self.player_id {
+            query = query.player_id(player_id);
+        }
+
+        if let Some(game_id) = &self.game_id {
+            query = query.game(game_id);
+        }
+
+        if let Some(team) = &self.team {
+            query = query.team(team);
+        }
+
+        if let Some(play_type) = &self.play_type {
+            query = query.play_type(play_type);
+        }
+
+        if let Some(down) = self.down {
+            query = query.down(down);
+        }
+
+        query = query.distance(self.min_ydstogo, self.max_ydstogo);
+
+        if let Some(weeks) = self.weeks {
+            query = weeks.filter(query);
+        }
+
+        Ok(query.limit(self.limit()).offset(self.offset()).sql())
+    }
+
+    fn limit(&self) -> u16 {
+        self.limit.unwrap_or(100).min(MAX_LIMIT)
+    }
+
+    fn offset(&self) -> u32 {
+        self.offset.unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct PlaysPage {
+    #[schema(value_type = Vec<Object>)]
+    plays: JsonRecords,
+    /// Offset of the next page, if there may be more plays
+    next_offset: Option<u32>,
+}
+
+/// Raw play-by-play rows, paginated
+#[utoipa::path(
+    get,
+    path = "/v1/plays",
+    params(ListPlaysParams),
+    responses(
+        (status = 200, description = "Page of plays", body = PlaysPage),
+        (status = 400, description = "Invalid query params"),
+    )
+)]
+pub(super) async fn list_plays(
+    State(ctx): State<Ctx>,
+    params: Query<ListPlaysParams>,
+) -> Result<Json<PlaysPage>> {
+    let query = params.make_query(ctx.config.years)?;
+    let plays = query_spice(&ctx, QueryKind::Plays, &query).await?;
+    let next_offset =
+        (plays.len() >= params.limit() as usize).then(|| params.offset() + plays.len() as u32);
+    Ok(Json(PlaysPage { plays, next_offset }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn check(params: serde_json::Value) -> Result<String> {
+        let params: ListPlaysParams = serde_json::from_value(params).unwrap();
+        params.make_query(Years::new(2023, 2023))
+    }
+
+    #[test]
+    fn rejects_invalid_params() {
+        assert!(check(json!({"min_ydstogo": 5, "max_ydstogo": 5})).is_ok());
+        assert!(check(json!({"min_ydstogo": 6, "max_ydstogo": 5})).is_err());
+        assert!(check(json!({"down": 5})).is_err());
+        assert!(check(json!({"limit": 0})).is_err());
+    }
+}
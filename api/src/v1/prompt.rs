@@ -0,0 +1,154 @@
+use super::{query_spice, QueryKind};
+use crate::{Ctx, Result};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Descriptions for the play-by-play columns the stats queries rely on
+const PBP_COLUMNS: &[(&str, &str)] = &[
+    (
+        "game_id",
+        "e.g. 2024_01_BAL_KC (season, week, away team, home team)",
+    ),
+    ("game_date", "date of the game"),
+    ("week", "1-18 regular season, 19-22 postseason"),
+    ("posteam", "team on offense"),
+    ("defteam", "team on defense"),
+    (
+        "play_type",
+        "pass, run, punt, field_goal, kickoff, extra_point, ...",
+    ),
+    ("down", "1-4, NULL on kicks"),
+    ("ydstogo", "yards needed for a first down"),
+    (
+        "yardline_100",
+        "yards from the opponent's end zone, <= 20 is the red zone",
+    ),
+    ("desc", "play description, quote as \"desc\""),
+    ("passer_player_id", "GSIS id, matches roster gsis_id"),
+    ("passer_player_name", "abbreviated, e.g. P.Mahomes"),
+    ("receiver_player_id", "GSIS id of the targeted receiver"),
+    ("receiver_player_name", "abbreviated, e.g. T.Kelce"),
+    ("rusher_player_id", "GSIS id of the ball carrier"),
+    ("rusher_player_name", "abbreviated, e.g. I.Pacheco"),
+    (
+        "passing_yards",
+        "yards on a completed pass, credited to the passer",
+    ),
+    (
+        "receiving_yards",
+        "yards on a completed pass, credited to the receiver",
+    ),
+    ("rushing_yards", "yards on a run"),
+    ("complete_pass", "1 for a completion"),
+    ("pass_touchdown", "1 for a passing touchdown"),
+    ("rush_touchdown", "1 for a rushing touchdown"),
+    ("interception", "1 for an interception"),
+    ("rush_attempt", "1 for a run"),
+    (
+        "two_point_conv_result",
+        "success or failure on two point tries",
+    ),
+    ("sp", "1 for scoring plays"),
+    ("total_home_score", "home score after the play"),
+    ("total_away_score", "away score after the play"),
+];
+
+/// Descriptions for the weekly roster columns
+const ROSTER_COLUMNS: &[(&str, &str)] = &[
+    (
+        "gsis_id",
+        "player id used by play-by-play *_player_id columns",
+    ),
+    ("full_name", "e.g. Patrick Mahomes"),
+    ("position", "QB, RB, WR, TE, K, ..."),
+    ("team", "team abbreviation, e.g. KC"),
+    ("week", "roster week"),
+    ("status", "ACT for active players"),
+    ("birth_date", "date of birth"),
+];
+
+/// Builds the chat system prompt from the dataset schemas, seasons, current week and scoring
+///
+/// The schema and week queries go through the query cache, so this is cheap to call per chat.
+pub(super) async fn system_prompt(ctx: &Ctx, with_tools: bool) -> Result<String> {
+    let years = ctx.config.years;
+    let latest = years.latest();
+    let (pbp, roster) = (format!("pbp{latest}"), format!("roster{latest}"));
+
+    let schema_query = format!(
+        "SELECT table_name, column_name, data_type FROM information_schema.columns
+        WHERE table_name IN ('{pbp}', '{roster}')
+        ORDER BY table_name, ordinal_position"
+    );
+    let columns = query_spice(ctx, QueryKind::Schema, &schema_query).await?;
+
+    let week_query = format!("SELECT MAX(week) AS week FROM {pbp}");
+    let current_week = query_spice(ctx, QueryKind::Schema, &week_query)
+        .await?
+        .first()
+        .and_then(|row| row.get("week"))
+        .and_then(Value::as_u64);
+
+    let mut prompt =
+        String::from("You are a fantasy football analyst answering questions about NFL stats.\n");
+    if with_tools {
+        prompt.push_str(
+            "Use the provided tools to look up stats instead of guessing or relying on memory.\n",
+        );
+    }
+    let progress = match current_week {
+        Some(week) => format!("with data through week {week}"),
+        None => "which hasn't started".to_string(),
+    };
+    let _ = writeln!(prompt, "\nSeasons available: {years}.");
+    let _ = writeln!(prompt, "The current season is {latest}, {progress}.");
+    prompt.push_str("Weeks 1-18 are the regular season and 19-22 the postseason.\n");
+    let _ = writeln!(prompt, "\nFantasy scoring is {}.", ctx.scoring.default());
+
+    let _ = writeln!(
+        prompt,
+        "\nDatasets are named by season, e.g. pbp{latest} and roster{latest}, with the same columns each season."
+    );
+    for (table, about, described) in [
+        (&pbp, "one row per play", PBP_COLUMNS),
+        (&roster, "one row per player per week", ROSTER_COLUMNS),
+    ] {
+        let _ = writeln!(prompt, "\n{table} ({about}) columns:");
+        write_columns(&mut prompt, table, &columns, described);
+    }
+
+    Ok(prompt)
+}
+
+/// Writes described columns one per line, followed by the names of the rest
+fn write_columns(
+    prompt: &mut String,
+    table: &str,
+    columns: &[Map<String, Value>],
+    described: &[(&str, &str)],
+) {
+    let descriptions: HashMap<_, _> = described.iter().copied().collect();
+    let mut others = Vec::new();
+    for column in columns {
+        if column.get("table_name").and_then(Value::as_str) != Some(table) {
+            continue;
+        }
+        let Some(name) = column.get("column_name").and_then(Value::as_str) else {
+            continue;
+        };
+        let data_type = column
+            .get("data_type")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown");
+        match descriptions.get(name) {
+            Some(description) => {
+                let _ = writeln!(prompt, "- {name} ({data_type}): {description}");
+            }
+            None => others.push(name),
+        }
+    }
+    if !others.is_empty() {
+        let _ = writeln!(prompt, "- also: {}", others.join(", "));
+    }
+}
@@ -0,0 +1,172 @@
+use super::{query_spice, season, JsonRecords, QueryKind, Weeks};
+use crate::{error::Error, extract::Query, serde_utils, Ctx, Result};
+use axum::{extract::State, Json};
+use fff::{
+    pbp::PbpQuery,
+    rankings::{self, League, Lineup},
+    scoring::Scoring,
+    tiers,
+    years::Years,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct GetRankingsParams {
+    year: Option<u16>,
+
+    /// Week number or range (e.g. 3 or 3-5)
+    #[serde(with = "serde_utils::string_opt", default)]
+    #[param(value_type = Option<String>)]
+    weeks: Option<Weeks>,
+    /// Named scoring config, defaults to the configured scoring
+    scoring: Option<String>,
+    /// Teams in the league (default 12)
+    teams: Option<u16>,
+
+    /// Starting lineup slots (default QB,RB,RB,WR,WR,TE,FLEX), may include SUPERFLEX
+    #[serde(with = "serde_utils::string_opt", default)]
+    #[param(value_type = Option<String>)]
+    lineup: Option<Lineup>,
+    /// Only return players at this position, replacement levels still consider every position
+    position: Option<String>,
+    /// Number of players to return (default 100)
+    n: Option<u16>,
+    /// Tiers per position, split at the largest gaps in fantasy points (default 6)
+    tiers: Option<u16>,
+    /// Named projections to tier on instead of season points, for the players they cover
+    projections: Option<String>,
+}
+
+impl GetRankingsParams {
+    /// Season totals with fantasy points for every player, best first
+    fn make_query(&self, years: Years, scoring: &Scoring) -> Result<String> {
+        tracing::trace!("GetRankingsParams {:?}", self);
+        // Sorted by fantasy points, so the row limit only drops players far below replacement
+        let mut query_builder = PbpQuery::year(season(years, self.year)?)
+            .join_roster()
+            .aggregate()
+            .fantasy_points(scoring);
+
+        if let Some(weeks) = self.weeks {
+            query_builder = weeks.filter(query_builder);
+        }
+
+        Ok(query_builder.sql())
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct RankingsResponse {
+    /// Fantasy points of the replacement-level player at each position
+    replacement: BTreeMap<String, f64>,
+    /// Season totals with `fantasy_points`, `vorp`, `pos_rank`, `overall_rank` and `tier`, by overall rank
+    ///
+    /// Also `projected_points` when tiered on `projections`
+    #[schema(value_type = Vec<Object>)]
+    players: JsonRecords,
+}
+
+/// Players ranked by fantasy points over replacement for a league's lineup
+///
+/// Replacement level at a position is the best player there who wouldn't start once every
+/// team fills its lineup, with FLEX and SUPERFLEX slots going to the best players left.
+#[utoipa::path(
+    get,
+    path = "/v1/rankings",
+    params(GetRankingsParams),
+    responses(
+        (status = 200, description = "Rankings with value over replacement", body = RankingsResponse),
+        (status = 400, description = "Invalid query params, lineup or scoring"),
+    )
+)]
+pub(super) async fn get_rankings(
+    State(ctx): State<Ctx>,
+    params: Query<GetRankingsParams>,
+) -> Result<Json<RankingsResponse>> {
+    let league = League {
+        teams: params.teams.unwrap_or(12),
+        lineup: params.lineup.clone().unwrap_or_default(),
+    };
+    if league.teams == 0 {
+        return Err(Error::bad_req("teams must be at least 1"));
+    }
+    let tier_count = params.tiers.unwrap_or(tiers::DEFAULT_TIERS);
+    if tier_count == 0 {
+        return Err(Error::bad_req("tiers must be at least 1"));
+    }
+    let scoring = ctx.scoring.resolve(params.scoring.as_deref())?;
+    let projections = ctx.projections.resolve(params.projections.as_deref())?;
+    let query = params.make_query(
+        ctx.config.years,
+        scoring.as_deref().unwrap_or(ctx.scoring.default()),
+    )?;
+    let players = query_spice(&ctx, QueryKind::Rankings, &query).await?;
+
+    let points: Vec<_> = players
+        .iter()
+        .map(|row| {
+            let position = row
+                .get("position")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let points = row
+                .get("fantasy_points")
+                .and_then(Value::as_f64)
+                .unwrap_or_default();
+            (position, points)
+        })
+        .collect();
+    let rankings = rankings::rank(&league, &points);
+    let projected: Vec<_> = match &projections {
+        Some(projections) => players
+            .iter()
+            .map(|row| {
+                let player_id = row.get("player_id").and_then(Value::as_str);
+                player_id.and_then(|player_id| projections.get(player_id))
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    let player_tiers = tiers::by_position(&points, &projected, &rankings.replacement, tier_count);
+
+    let mut ranked: Vec<_> = players
+        .into_iter()
+        .zip(rankings.players)
+        .zip(player_tiers)
+        .enumerate()
+        .filter_map(|(i, ((mut row, ranking), tier))| {
+            let ranking = ranking?;
+            if projections.is_some() {
+                row.insert("projected_points".into(), projected[i].into());
+            }
+            row.insert(
+                "vorp".into(),
+                ((ranking.vorp * 100.0).round() / 100.0).into(),
+            );
+            row.insert("pos_rank".into(), ranking.pos_rank.into());
+            row.insert("overall_rank".into(), ranking.overall_rank.into());
+            row.insert("tier".into(), tier.into());
+            Some((ranking.overall_rank, row))
+        })
+        .collect();
+    ranked.sort_by_key(|(overall_rank, _)| *overall_rank);
+
+    let position = params.position.as_deref().map(str::to_uppercase);
+    let players = ranked
+        .into_iter()
+        .map(|(_, row)| row)
+        .filter(|row| {
+            position.is_none() || row.get("position").and_then(Value::as_str) == position.as_deref()
+        })
+        .take(usize::from(params.n.unwrap_or(100)))
+        .collect();
+
+    Ok(Json(RankingsResponse {
+        replacement: rankings.replacement,
+        players,
+    }))
+}
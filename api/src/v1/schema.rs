@@ -0,0 +1,42 @@
+use super::{query_spice, QueryKind};
+use crate::{Ctx, Result};
+use fff::schema;
+use serde_json::Value;
+
+/// Logs any drift between each season's datasets and the columns the fff builders reference
+///
+/// Returns the number of problems found, so nflverse column renames show up at startup
+/// rather than as failing queries.
+pub(crate) async fn check_drift(ctx: &Ctx) -> Result<usize> {
+    let mut problems = 0;
+    // One season at a time keeps each result well under the JSON row limit
+    for year in ctx.config.years.iter() {
+        let columns = query_spice(ctx, QueryKind::Schema, &schema::columns_query([year])).await?;
+        let rows: Vec<_> = columns
+            .iter()
+            .map(|row| {
+                let field = |name: &str| {
+                    row.get(name)
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                (
+                    field("table_name"),
+                    field("column_name"),
+                    field("data_type"),
+                )
+            })
+            .collect();
+
+        let drift = schema::check_year(year, &rows);
+        for (table, found) in &drift {
+            tracing::warn!("schema drift in {table}: {found}");
+        }
+        problems += drift.len();
+    }
+    if problems == 0 {
+        tracing::info!("dataset schemas match for {}", ctx.config.years);
+    }
+    Ok(problems)
+}
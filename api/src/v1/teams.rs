@@ -0,0 +1,70 @@
+use super::{query_spice, season, JsonRecords, QueryKind, Weeks};
+use crate::{extract::Query, serde_utils, Ctx, Result};
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use fff::{pbp::PbpQuery, teams::TeamQuery};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub(super) struct GetTeamParams {
+    year: Option<u16>,
+
+    /// Week number or range (e.g. 3 or 3-5)
+    #[serde(with = "serde_utils::string_opt", default)]
+    #[param(value_type = Option<String>)]
+    weeks: Option<Weeks>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct TeamSummary {
+    team: String,
+    /// Offensive totals per game
+    #[schema(value_type = Vec<Object>)]
+    games: JsonRecords,
+    /// Per-player usage with shares of team targets, carries and red zone touches
+    #[schema(value_type = Vec<Object>)]
+    players: JsonRecords,
+}
+
+/// Team weekly offensive totals and player usage shares
+#[utoipa::path(
+    get,
+    path = "/v1/teams/{team}",
+    params(("team" = String, Path, description = "Team abbreviation (e.g. KC)"), GetTeamParams),
+    responses(
+        (status = 200, description = "Team summary", body = TeamSummary),
+        (status = 400, description = "Invalid query params"),
+    )
+)]
+pub(super) async fn get_team(
+    State(ctx): State<Ctx>,
+    Path(team): Path<String>,
+    params: Query<GetTeamParams>,
+) -> Result<Json<TeamSummary>> {
+    tracing::trace!("GetTeamParams {:?}", params);
+    let team = team.to_uppercase();
+    let year = season(ctx.config.years, params.year)?;
+
+    let mut games = TeamQuery::new(year).team(&team);
+    let mut players = PbpQuery::year(year)
+        .filter_team(&team)
+        .join_roster()
+        .usage_shares();
+    if let Some(weeks) = params.weeks {
+        games = weeks.filter(games);
+        players = weeks.filter(players);
+    }
+
+    let games = query_spice(&ctx, QueryKind::Teams, &games.sql()).await?;
+    let players = query_spice(&ctx, QueryKind::Teams, &players.sql()).await?;
+
+    Ok(Json(TeamSummary {
+        team,
+        games,
+        players,
+    }))
+}
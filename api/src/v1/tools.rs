@@ -0,0 +1,249 @@
+use super::{
+    leaders::GetLeadersParams, query_spice, season, JsonRecords, QueryKind, SearchPlayersParams,
+    Weeks,
+};
+use crate::{error::Error, serde_utils, Ctx, Result};
+use async_openai::error::OpenAIError;
+use async_openai::types::{
+    ChatCompletionMessageToolCall, ChatCompletionMessageToolCallChunk,
+    ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage,
+    ChatCompletionRequestToolMessageArgs, ChatCompletionTool, ChatCompletionToolArgs,
+    ChatCompletionToolType, FunctionCall, FunctionObjectArgs,
+};
+use fff::pbp::{PbpQuery, STAT_COLUMNS};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Most rows returned to the model from a single tool call, to keep prompts small
+const MAX_TOOL_ROWS: usize = 50;
+
+/// Function tools offered to the model, each backed by an `fff` query builder
+pub(super) fn definitions() -> Result<Vec<ChatCompletionTool>, OpenAIError> {
+    let weeks = json!({
+        "type": "string",
+        "description": "Week number or range, e.g. \"3\" or \"3-5\"",
+    });
+    let year = json!({
+        "type": "integer",
+        "description": "Season, defaults to the latest season",
+    });
+    let position = json!({
+        "type": "string",
+        "description": "Roster position, e.g. QB, RB, WR or TE",
+    });
+
+    [
+        (
+            "get_player_stats",
+            "Season totals and fantasy points for a player, or their game log when per_game is true",
+            json!({
+                "type": "object",
+                "properties": {
+                    "player": {"type": "string", "description": "Full or partial player name"},
+                    "year": year,
+                    "weeks": weeks,
+                    "per_game": {"type": "boolean", "description": "Return one row per game"},
+                },
+                "required": ["player"],
+            }),
+        ),
+        (
+            "search_players",
+            "Find roster entries (team, position, ids) by player name",
+            json!({
+                "type": "object",
+                "properties": {
+                    "search": {"type": "string", "description": "Case-insensitive part of a name"},
+                    "year": year,
+                    "week": {"type": "integer", "description": "Roster week"},
+                },
+                "required": ["search"],
+            }),
+        ),
+        (
+            "leaders",
+            "Players ranked by season totals of a stat, with overall and positional rank and fantasy points",
+            json!({
+                "type": "object",
+                "properties": {
+                    "stat": {"type": "string", "enum": STAT_COLUMNS},
+                    "year": year,
+                    "position": position,
+                    "weeks": weeks,
+                    "team": {"type": "string", "description": "Team abbreviation, e.g. KC"},
+                    "n": {"type": "integer", "description": "Number of players, defaults to 24"},
+                    "scoring": {"type": "string", "description": "Scoring config for fantasy points, e.g. ppr"},
+                },
+                "required": ["stat"],
+            }),
+        ),
+        (
+            "matchups",
+            "Stats opposing players recorded against a defense, to judge how a matchup favors a position",
+            json!({
+                "type": "object",
+                "properties": {
+                    "defense": {"type": "string", "description": "Defensive team abbreviation, e.g. KC"},
+                    "position": position,
+                    "year": year,
+                    "weeks": weeks,
+                },
+                "required": ["defense"],
+            }),
+        ),
+    ]
+    .into_iter()
+    .map(|(name, description, parameters)| {
+        ChatCompletionToolArgs::default()
+            .function(
+                FunctionObjectArgs::default()
+                    .name(name)
+                    .description(description)
+                    .parameters(parameters)
+                    .build()?,
+            )
+            .build()
+    })
+    .collect()
+}
+
+/// Merges streamed tool call fragments into complete tool calls, indexed by position
+pub(super) fn accumulate(
+    calls: &mut Vec<ChatCompletionMessageToolCall>,
+    chunks: &[ChatCompletionMessageToolCallChunk],
+) {
+    for chunk in chunks {
+        let index = chunk.index as usize;
+        if calls.len() <= index {
+            calls.resize_with(index + 1, || ChatCompletionMessageToolCall {
+                id: String::new(),
+                r#type: ChatCompletionToolType::Function,
+                function: FunctionCall {
+                    name: String::new(),
+                    arguments: String::new(),
+                },
+            });
+        }
+        let call = &mut calls[index];
+        if let Some(id) = &chunk.id {
+            call.id.push_str(id);
+        }
+        if let Some(function) = &chunk.function {
+            if let Some(name) = &function.name {
+                call.function.name.push_str(name);
+            }
+            if let Some(arguments) = &function.arguments {
+                call.function.arguments.push_str(arguments);
+            }
+        }
+    }
+}
+
+/// Runs the model's tool calls, appending them and their results to the conversation
+pub(super) async fn resolve(
+    ctx: &Ctx,
+    messages: &mut Vec<ChatCompletionRequestMessage>,
+    calls: Vec<ChatCompletionMessageToolCall>,
+) -> Result<(), OpenAIError> {
+    messages.push(
+        ChatCompletionRequestAssistantMessageArgs::default()
+            .tool_calls(calls.clone())
+            .build()?
+            .into(),
+    );
+    for ChatCompletionMessageToolCall { id, function, .. } in calls {
+        let content = call(ctx, &function.name, &function.arguments).await;
+        messages.push(
+            ChatCompletionRequestToolMessageArgs::default()
+                .content(content)
+                .tool_call_id(id)
+                .build()?
+                .into(),
+        );
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct PlayerStatsArgs {
+    player: String,
+    year: Option<u16>,
+    #[serde(with = "serde_utils::string_opt", default)]
+    weeks: Option<Weeks>,
+    #[serde(default)]
+    per_game: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct MatchupsArgs {
+    defense: String,
+    position: Option<String>,
+    year: Option<u16>,
+    #[serde(with = "serde_utils::string_opt", default)]
+    weeks: Option<Weeks>,
+}
+
+/// Runs a tool call from the model and returns the JSON content of the tool message
+///
+/// Failures are returned to the model as an `error` object so it can correct its arguments.
+async fn call(ctx: &Ctx, name: &str, arguments: &str) -> String {
+    tracing::info!("tool call {name}: {arguments}");
+    match run(ctx, name, arguments).await {
+        Ok(mut records) => {
+            records.truncate(MAX_TOOL_ROWS);
+            Value::from(records).to_string()
+        }
+        Err(err) => {
+            tracing::warn!("tool call {name} failed: {err:?}");
+            json!({"error": err.http_message()}).to_string()
+        }
+    }
+}
+
+async fn run(ctx: &Ctx, name: &str, arguments: &str) -> Result<JsonRecords> {
+    let parse_err = |err: serde_json::Error| Error::bad_req(format!("invalid arguments: {err}"));
+    match name {
+        "get_player_stats" => {
+            let args: PlayerStatsArgs = serde_json::from_str(arguments).map_err(parse_err)?;
+            let mut query = PbpQuery::year(season(ctx.config.years, args.year)?)
+                .filter_player(&args.player)
+                .fantasy_points(ctx.scoring.default())
+                .limit(MAX_TOOL_ROWS as u16);
+            if let Some(weeks) = args.weeks {
+                query = weeks.filter(query);
+            }
+            if !args.per_game {
+                query = query.aggregate();
+            }
+            query_spice(ctx, QueryKind::Chat, &query.sql()).await
+        }
+        "search_players" => {
+            let args: SearchPlayersParams = serde_json::from_str(arguments).map_err(parse_err)?;
+            query_spice(ctx, QueryKind::Chat, &args.make_query(ctx.config.years)?).await
+        }
+        "leaders" => {
+            let mut args: GetLeadersParams = serde_json::from_str(arguments).map_err(parse_err)?;
+            args.n = args.n.map(|n| n.min(MAX_TOOL_ROWS as u16));
+            let scoring = ctx.scoring.resolve(args.scoring.as_deref())?;
+            let scoring = scoring.as_deref().unwrap_or(ctx.scoring.default());
+            let query = args.make_query(ctx.config.years, Some(scoring))?;
+            query_spice(ctx, QueryKind::Chat, &query).await
+        }
+        "matchups" => {
+            let args: MatchupsArgs = serde_json::from_str(arguments).map_err(parse_err)?;
+            let mut query = PbpQuery::year(season(ctx.config.years, args.year)?)
+                .filter_opponent(&args.defense.to_uppercase())
+                .join_roster()
+                .aggregate()
+                .limit(MAX_TOOL_ROWS as u16);
+            if let Some(position) = &args.position {
+                query = query.filter_position(position);
+            }
+            if let Some(weeks) = args.weeks {
+                query = weeks.filter(query);
+            }
+            query_spice(ctx, QueryKind::Chat, &query.sql()).await
+        }
+        _ => Err(Error::bad_req(format!("unknown tool: {name}"))),
+    }
+}
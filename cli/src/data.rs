@@ -0,0 +1,533 @@
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use fff::datasets::{Dataset, Source};
+use fff::years::Years;
+use reqwest::header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use time::OffsetDateTime;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const MANIFEST: &str = "manifest.json";
+/// Bumped when the manifest layout changes, older caches are re-synced from scratch
+const MANIFEST_VERSION: u32 = 1;
+/// Versions of each dataset kept on disk, the newest plus one to roll back to
+const KEEP_VERSIONS: usize = 2;
+
+#[derive(Subcommand, Debug)]
+pub enum DataCommand {
+    /// Download nflverse data into the local cache, skipping unchanged past seasons
+    Sync {
+        /// Season or range of seasons (e.g. 2020-2024)
+        #[arg(long, env = "YEARS", default_value = "2023-2024")]
+        years: Years,
+
+        /// Comma separated datasets: pbp, rosters, schedules, snaps
+        #[arg(long, value_delimiter = ',', default_value = "pbp,rosters")]
+        datasets: Vec<Dataset>,
+
+        /// Season that is still being played, defaults to the latest of `years`
+        #[arg(long)]
+        current: Option<u16>,
+
+        /// Download every dataset again, even if unchanged
+        #[arg(long)]
+        force: bool,
+
+        /// Fetch files by name from this URL instead of nflverse (e.g. a local HTTP server)
+        #[arg(long)]
+        mirror: Option<String>,
+
+        #[arg(long, env = "MELANGE_DATA_DIR", default_value = "data")]
+        data_dir: PathBuf,
+    },
+    /// List the cached datasets and their versions
+    Status {
+        #[arg(long, env = "MELANGE_DATA_DIR", default_value = "data")]
+        data_dir: PathBuf,
+    },
+}
+
+/// Index of the cache, stored as `manifest.json` in the data directory
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    /// Keyed by dataset name, e.g. `pbp2024`
+    datasets: BTreeMap<String, Entry>,
+}
+
+/// The cached version of one dataset
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    year: Option<u16>,
+    /// Path relative to the data directory, named by checksum
+    file: String,
+    sha256: String,
+    bytes: u64,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    fetched_at: OffsetDateTime,
+    /// Earlier versions still on disk, newest first
+    #[serde(default)]
+    previous: Vec<String>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Manifest {
+            version: MANIFEST_VERSION,
+            datasets: BTreeMap::new(),
+        }
+    }
+}
+
+impl Manifest {
+    fn load(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(MANIFEST);
+        if !path.exists() {
+            return Ok(Manifest::default());
+        }
+        let json = std::fs::read_to_string(&path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        let manifest: Manifest =
+            serde_json::from_str(&json).with_context(|| format!("parsing {}", path.display()))?;
+        if manifest.version != MANIFEST_VERSION {
+            eprintln!(
+                "Ignoring manifest version {} (expected {MANIFEST_VERSION})",
+                manifest.version
+            );
+            return Ok(Manifest::default());
+        }
+        Ok(manifest)
+    }
+
+    /// Writes the manifest via a temporary file so an interrupted sync can't corrupt it
+    fn save(&self, data_dir: &Path) -> Result<()> {
+        let path = data_dir.join(MANIFEST);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+pub async fn run(command: DataCommand) -> Result<()> {
+    match command {
+        DataCommand::Sync {
+            years,
+            datasets,
+            current,
+            force,
+            mirror,
+            data_dir,
+        } => {
+            let current = current.unwrap_or(years.latest());
+            sync(
+                &data_dir,
+                years,
+                &datasets,
+                current,
+                force,
+                mirror.as_deref(),
+            )
+            .await
+        }
+        DataCommand::Status { data_dir } => status(&data_dir),
+    }
+}
+
+/// What a sync did with one dataset
+enum Outcome {
+    /// Past season already cached with a matching checksum, no request made
+    Skipped,
+    /// The server reported the file unchanged, or the download matched the cached checksum
+    Unchanged,
+    Downloaded {
+        bytes: u64,
+    },
+}
+
+async fn sync(
+    data_dir: &Path,
+    years: Years,
+    datasets: &[Dataset],
+    current: u16,
+    force: bool,
+    mirror: Option<&str>,
+) -> Result<()> {
+    std::fs::create_dir_all(data_dir)
+        .with_context(|| format!("creating {}", data_dir.display()))?;
+    let mut manifest = Manifest::load(data_dir)?;
+    let http = reqwest::Client::new();
+
+    let mut failures = 0;
+    for dataset in datasets {
+        for source in dataset.sources(years.iter().rev()) {
+            let url = match mirror {
+                Some(mirror) => mirror_url(mirror, &source.url),
+                None => source.url.clone(),
+            };
+            let outcome = sync_source(
+                &http,
+                data_dir,
+                &mut manifest,
+                &source,
+                &url,
+                current,
+                force,
+            )
+            .await;
+            match outcome {
+                Ok(Outcome::Skipped) => println!("{}: up to date", source.name),
+                Ok(Outcome::Unchanged) => println!("{}: unchanged", source.name),
+                Ok(Outcome::Downloaded { bytes }) => {
+                    println!("{}: downloaded {}", source.name, megabytes(bytes))
+                }
+                Err(err) => {
+                    eprintln!("{}: {err:#}", source.name);
+                    failures += 1;
+                }
+            }
+            // Saved after each dataset so progress survives a failed or interrupted sync
+            manifest.save(data_dir)?;
+        }
+    }
+
+    if failures > 0 {
+        bail!("{failures} datasets failed to sync");
+    }
+    Ok(())
+}
+
+async fn sync_source(
+    http: &reqwest::Client,
+    data_dir: &Path,
+    manifest: &mut Manifest,
+    source: &Source,
+    url: &str,
+    current: u16,
+    force: bool,
+) -> Result<Outcome> {
+    let cached = manifest
+        .datasets
+        .get(&source.name)
+        .filter(|entry| !force && entry.url == url);
+    let cached_ok = match cached {
+        Some(entry) => checksum(&data_dir.join(&entry.file))
+            .await
+            .is_ok_and(|sha256| sha256 == entry.sha256),
+        None => false,
+    };
+    // Past seasons only see rare corrections, use `--force` to pick those up
+    let past = source.year.is_some_and(|year| year < current);
+    if cached_ok && past {
+        return Ok(Outcome::Skipped);
+    }
+
+    let mut request = http.get(url);
+    if let Some(entry) = cached.filter(|_| cached_ok) {
+        if let Some(etag) = &entry.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let mut response = request.send().await?;
+    if response.status() == StatusCode::NOT_MODIFIED && cached_ok {
+        if let Some(entry) = manifest.datasets.get_mut(&source.name) {
+            entry.fetched_at = OffsetDateTime::now_utc();
+        }
+        return Ok(Outcome::Unchanged);
+    }
+    if !response.status().is_success() {
+        bail!("GET {url}: {}", response.status());
+    }
+    let header = |name: HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+
+    // Stream to a temporary file, hashing as we go, then move it into place by checksum
+    let dir = data_dir.join(&source.name);
+    tokio::fs::create_dir_all(&dir).await?;
+    let tmp = dir.join(".download");
+    let mut file = tokio::fs::File::create(&tmp).await?;
+    let mut hasher = Sha256::new();
+    let mut bytes = 0;
+    while let Some(chunk) = response.chunk().await? {
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+        bytes += chunk.len() as u64;
+    }
+    file.flush().await?;
+    let sha256 = format!("{:x}", hasher.finalize());
+
+    let unchanged = cached_ok && cached.is_some_and(|entry| entry.sha256 == sha256);
+    let file_name = format!("{}/{}.{}", source.name, &sha256[..12], source.file_format);
+    tokio::fs::rename(&tmp, data_dir.join(&file_name)).await?;
+
+    let mut previous = Vec::new();
+    if let Some(old) = manifest.datasets.remove(&source.name) {
+        if old.file != file_name {
+            previous.push(old.file);
+        }
+        previous.extend(old.previous.into_iter().filter(|file| *file != file_name));
+    }
+    for stale in previous.split_off((KEEP_VERSIONS - 1).min(previous.len())) {
+        let _ = tokio::fs::remove_file(data_dir.join(stale)).await;
+    }
+    manifest.datasets.insert(
+        source.name.clone(),
+        Entry {
+            url: url.to_string(),
+            year: source.year,
+            file: file_name,
+            sha256,
+            bytes,
+            etag,
+            last_modified,
+            fetched_at: OffsetDateTime::now_utc(),
+            previous,
+        },
+    );
+
+    if unchanged {
+        Ok(Outcome::Unchanged)
+    } else {
+        Ok(Outcome::Downloaded { bytes })
+    }
+}
+
+fn status(data_dir: &Path) -> Result<()> {
+    let manifest = Manifest::load(data_dir)?;
+    if manifest.datasets.is_empty() {
+        println!("No data in {}, run `melange data sync`", data_dir.display());
+        return Ok(());
+    }
+    for (name, entry) in &manifest.datasets {
+        let present = data_dir.join(&entry.file).exists();
+        let fetched = entry
+            .fetched_at
+            .format(&time::format_description::well_known::Rfc3339)?;
+        println!(
+            "{name:<14} {:>9}  {}  fetched {fetched}{}",
+            megabytes(entry.bytes),
+            &entry.sha256[..12],
+            if present { "" } else { "  (file missing)" },
+        );
+    }
+    Ok(())
+}
+
+/// SHA-256 of a file, as lowercase hex
+async fn checksum(path: &Path) -> Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Swaps the host and path of an nflverse URL for a mirror, keeping the file name
+fn mirror_url(mirror: &str, url: &str) -> String {
+    let file = url.rsplit('/').next().unwrap_or(url);
+    format!("{}/{file}", mirror.trim_end_matches('/'))
+}
+
+fn megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / 1_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Path as UrlPath, State};
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::get;
+    use axum::Router;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    /// Files served by the mirror, with an ETag derived from their contents
+    #[derive(Default)]
+    struct Files {
+        contents: HashMap<String, Vec<u8>>,
+        requests: usize,
+    }
+
+    type Mirror = Arc<Mutex<Files>>;
+
+    async fn serve_file(
+        State(mirror): State<Mirror>,
+        UrlPath(file): UrlPath<String>,
+        headers: HeaderMap,
+    ) -> Response {
+        let mut files = mirror.lock().unwrap();
+        files.requests += 1;
+        let Some(contents) = files.contents.get(&file) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        let etag = format!("\"{:x}\"", Sha256::digest(contents));
+        if headers
+            .get(IF_NONE_MATCH)
+            .is_some_and(|value| *value == *etag)
+        {
+            return StatusCode::NOT_MODIFIED.into_response();
+        }
+        ([(ETAG, etag)], contents.clone()).into_response()
+    }
+
+    /// Starts a mirror and returns it with its URL
+    async fn mirror() -> (Mirror, String) {
+        let mirror = Mirror::default();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new()
+            .route("/:file", get(serve_file))
+            .with_state(mirror.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (mirror, format!("http://{addr}"))
+    }
+
+    fn data_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("melange-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn pbp(year: u16) -> Source {
+        Dataset::Pbp.sources([year]).remove(0)
+    }
+
+    /// Puts `contents` on the mirror under the file name of `source`
+    fn publish(mirror: &Mirror, source: &Source, contents: &str) {
+        let file = mirror_url("", &source.url)
+            .trim_start_matches('/')
+            .to_string();
+        let mut files = mirror.lock().unwrap();
+        files.contents.insert(file, contents.as_bytes().to_vec());
+    }
+
+    /// Syncs one source into `data_dir` through `url`, saving the manifest like `sync` does
+    async fn sync_one(data_dir: &Path, url: &str, source: &Source, current: u16) -> Outcome {
+        std::fs::create_dir_all(data_dir).unwrap();
+        let mut manifest = Manifest::load(data_dir).unwrap();
+        let url = mirror_url(url, &source.url);
+        let http = reqwest::Client::new();
+        let outcome = sync_source(&http, data_dir, &mut manifest, source, &url, current, false)
+            .await
+            .unwrap();
+        manifest.save(data_dir).unwrap();
+        outcome
+    }
+
+    fn cached(data_dir: &Path, source: &Source) -> Entry {
+        let mut manifest = Manifest::load(data_dir).unwrap();
+        manifest.datasets.remove(&source.name).unwrap()
+    }
+
+    fn requests(mirror: &Mirror) -> usize {
+        mirror.lock().unwrap().requests
+    }
+
+    #[tokio::test]
+    async fn downloads_new_datasets() {
+        let (mirror, url) = mirror().await;
+        let dir = data_dir("downloads");
+        let source = pbp(2024);
+        publish(&mirror, &source, "week 1");
+
+        let outcome = sync_one(&dir, &url, &source, 2024).await;
+        assert!(matches!(outcome, Outcome::Downloaded { bytes: 6 }));
+        let entry = cached(&dir, &source);
+        assert_eq!(
+            std::fs::read_to_string(dir.join(&entry.file)).unwrap(),
+            "week 1"
+        );
+        assert_eq!(
+            entry.sha256,
+            checksum(&dir.join(&entry.file)).await.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_cached_past_seasons() {
+        let (mirror, url) = mirror().await;
+        let dir = data_dir("skips");
+        let source = pbp(2023);
+        publish(&mirror, &source, "final");
+
+        sync_one(&dir, &url, &source, 2024).await;
+        let outcome = sync_one(&dir, &url, &source, 2024).await;
+        assert!(matches!(outcome, Outcome::Skipped));
+        assert_eq!(requests(&mirror), 1);
+    }
+
+    #[tokio::test]
+    async fn not_modified_is_unchanged() {
+        let (mirror, url) = mirror().await;
+        let dir = data_dir("not-modified");
+        let source = pbp(2024);
+        publish(&mirror, &source, "week 1");
+
+        sync_one(&dir, &url, &source, 2024).await;
+        let file = cached(&dir, &source).file;
+        let outcome = sync_one(&dir, &url, &source, 2024).await;
+        assert!(matches!(outcome, Outcome::Unchanged));
+        assert_eq!(requests(&mirror), 2);
+        assert_eq!(cached(&dir, &source).file, file);
+    }
+
+    #[tokio::test]
+    async fn checksum_mismatch_downloads_again() {
+        let (mirror, url) = mirror().await;
+        let dir = data_dir("checksum");
+        let source = pbp(2023);
+        publish(&mirror, &source, "final");
+
+        sync_one(&dir, &url, &source, 2024).await;
+        let file = dir.join(cached(&dir, &source).file);
+        std::fs::write(&file, "corrupt").unwrap();
+
+        let outcome = sync_one(&dir, &url, &source, 2024).await;
+        assert!(matches!(outcome, Outcome::Downloaded { .. }));
+        assert_eq!(requests(&mirror), 2);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "final");
+    }
+
+    #[tokio::test]
+    async fn keeps_the_newest_versions() {
+        let (mirror, url) = mirror().await;
+        let dir = data_dir("prunes");
+        let source = pbp(2024);
+
+        let mut files = Vec::new();
+        for week in 1..=3 {
+            publish(&mirror, &source, &format!("week {week}"));
+            sync_one(&dir, &url, &source, 2024).await;
+            files.push(cached(&dir, &source).file);
+        }
+
+        let entry = cached(&dir, &source);
+        assert_eq!(entry.previous, [files[1].clone()]);
+        assert!(!dir.join(&files[0]).exists());
+        let on_disk = std::fs::read_dir(dir.join(&source.name)).unwrap().count();
+        assert_eq!(on_disk, KEEP_VERSIONS);
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::{anyhow, bail, Context, Result};
+use arrow::array::{AsArray, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::DataType;
+use fff::schema;
+use fff::years::Years;
+use futures::TryStreamExt;
+use spiceai::Client;
+
+/// Checks every season's datasets have the columns the query builders reference
+///
+/// Prints the drift found per season and fails if there is any.
+pub async fn run(spice: &mut Client, years: Years, verbose: u8) -> Result<()> {
+    let sql = schema::columns_query(years.iter());
+    if verbose > 0 {
+        println!("query: {sql}");
+    }
+
+    let batches: Vec<_> = spice
+        .query(&sql)
+        .await
+        .map_err(|err| anyhow!("Query error: {}", err))?
+        .try_collect()
+        .await?;
+    let mut rows = Vec::new();
+    for batch in &batches {
+        rows.extend(column_rows(batch)?);
+    }
+
+    let mut problems = 0;
+    for year in years.iter() {
+        let drift = schema::check_year(year, &rows);
+        if drift.is_empty() {
+            println!("{year}: ok");
+            continue;
+        }
+        println!("{year}:");
+        for (table, found) in &drift {
+            println!("  {table}: {found}");
+        }
+        problems += drift.len();
+    }
+
+    if problems > 0 {
+        bail!("found {problems} schema problems, queries against those seasons may fail");
+    }
+    Ok(())
+}
+
+/// `(table_name, column_name, data_type)` rows of an `information_schema.columns` batch
+fn column_rows(batch: &RecordBatch) -> Result<Vec<(String, String, String)>> {
+    let strings = |name: &str| -> Result<Vec<String>> {
+        let column = batch
+            .column_by_name(name)
+            .with_context(|| format!("missing column {name}"))?;
+        let column = cast(column, &DataType::Utf8)?;
+        Ok(column
+            .as_string::<i32>()
+            .iter()
+            .map(|value| value.unwrap_or_default().to_string())
+            .collect())
+    };
+    let tables = strings("table_name")?;
+    let columns = strings("column_name")?;
+    let data_types = strings("data_type")?;
+    Ok(tables
+        .into_iter()
+        .zip(columns)
+        .zip(data_types)
+        .map(|((table, column), data_type)| (table, column, data_type))
+        .collect())
+}
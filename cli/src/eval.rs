@@ -0,0 +1,269 @@
+use crate::WeekArg;
+use anyhow::{anyhow, bail, Context, Result};
+use arrow::array::{Array, AsArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Float64Type};
+use async_openai::config::OpenAIConfig;
+use async_openai::error::OpenAIError;
+use async_openai::types::{
+    ChatCompletionRequestMessage, ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use fff::pbp::{self, PbpQuery};
+use futures::{StreamExt, TryStreamExt};
+use serde::Deserialize;
+use spiceai::Client;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+#[derive(clap::Args, Debug)]
+pub struct EvalArgs {
+    /// JSONL file of questions, each with the stat its answer should state
+    file: PathBuf,
+
+    /// Base URL of an OpenAI-compatible chat API, e.g. the melange API or a local mock
+    #[arg(long, default_value = "http://localhost:8888/v1")]
+    endpoint: String,
+
+    /// Model to ask
+    #[arg(long, default_value = "openai-with-fff")]
+    model: String,
+
+    /// API key sent as a bearer token
+    #[arg(long, env = "MELANGE_API_KEY")]
+    api_key: Option<String>,
+
+    /// Largest difference from the expected value that still counts as correct
+    #[arg(long, default_value_t = 0.5)]
+    tolerance: f64,
+}
+
+/// A question and the fact its answer should contain, e.g.
+///
+/// ```json
+/// {"question": "How many receiving yards did CeeDee Lamb have in 2023?", "player": "CeeDee Lamb", "stat": "receiving_yards", "year": 2023}
+/// ```
+#[derive(Debug, Deserialize)]
+struct EvalCase {
+    question: String,
+    player: String,
+    stat: String,
+    year: u16,
+    /// Week number or range (e.g. "3" or "3-5"), defaults to the whole season
+    weeks: Option<String>,
+}
+
+/// Asks each question and checks the answer mentions the stat as computed by [`PbpQuery`]
+pub async fn run(spice: &mut Client, args: EvalArgs, verbose: u8) -> Result<()> {
+    let contents = std::fs::read_to_string(&args.file)
+        .with_context(|| format!("reading {}", args.file.display()))?;
+    let cases: Vec<EvalCase> = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).with_context(|| format!("{}:{}", args.file.display(), i + 1))
+        })
+        .collect::<Result<_>>()?;
+
+    let mut config = OpenAIConfig::new().with_api_base(&args.endpoint);
+    if let Some(api_key) = &args.api_key {
+        config = config.with_api_key(api_key);
+    }
+    let openai = async_openai::Client::with_config(config);
+
+    let mut correct = 0;
+    for case in &cases {
+        let expected = match expected_value(spice, case, verbose).await {
+            Ok(expected) => expected,
+            Err(err) => {
+                println!(
+                    "[ERROR] {}\n  computing expected value: {err:#}",
+                    case.question
+                );
+                continue;
+            }
+        };
+        let answer = match ask(&openai, &args.model, &case.question).await {
+            Ok(answer) => answer,
+            Err(err) => {
+                println!("[ERROR] {}\n  asking model: {err:#}", case.question);
+                continue;
+            }
+        };
+
+        let passed = states_value(&answer, &case.question, expected, args.tolerance);
+        if passed {
+            correct += 1;
+        }
+        println!(
+            "[{}] {}\n  expected {expected} {}",
+            if passed { "PASS" } else { "FAIL" },
+            case.question,
+            case.stat,
+        );
+        if !passed || verbose > 0 {
+            println!("  answer: {}", answer.trim());
+        }
+    }
+
+    let total = cases.len();
+    let accuracy = if total > 0 {
+        100.0 * correct as f64 / total as f64
+    } else {
+        0.0
+    };
+    println!("\naccuracy: {correct}/{total} ({accuracy:.1}%)");
+    Ok(())
+}
+
+/// Season (or week range) total of the case's stat for its player
+async fn expected_value(spice: &mut Client, case: &EvalCase, verbose: u8) -> Result<f64> {
+    if !pbp::is_stat_column(&case.stat) {
+        bail!("unknown stat: {}", case.stat);
+    }
+
+    let mut query = PbpQuery::year(case.year)
+        .filter_player(&case.player)
+        .aggregate();
+    match case.weeks.as_deref().map(str::parse::<WeekArg>) {
+        Some(Ok(weeks)) => query = weeks.filter(query),
+        Some(Err(_)) => bail!("invalid weeks: {:?}", case.weeks),
+        None => {}
+    }
+    let sql = query.sql();
+    if verbose > 1 {
+        println!("query: {sql}");
+    }
+
+    let batches: Vec<_> = spice
+        .query(&sql)
+        .await
+        .map_err(|err| anyhow!("Query error: {}", err))?
+        .try_collect()
+        .await?;
+    let rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+    if rows != 1 {
+        bail!(
+            "expected one player matching {:?}, found {rows}",
+            case.player
+        );
+    }
+
+    let batch = batches.iter().find(|batch| batch.num_rows() > 0).unwrap();
+    let column = batch
+        .column_by_name(&case.stat)
+        .with_context(|| format!("missing column {}", case.stat))?;
+    let values = cast(column, &DataType::Float64)?;
+    let values = values.as_primitive::<Float64Type>();
+    // Players without any of the stat have NULL totals
+    Ok(if values.is_null(0) {
+        0.0
+    } else {
+        values.value(0)
+    })
+}
+
+/// Sends a question to the model and collects the streamed answer
+async fn ask(
+    openai: &async_openai::Client<OpenAIConfig>,
+    model: &str,
+    question: &str,
+) -> Result<String> {
+    let message: ChatCompletionRequestMessage = ChatCompletionRequestUserMessageArgs::default()
+        .content(question)
+        .build()?
+        .into();
+    let request = CreateChatCompletionRequestArgs::default()
+        .model(model)
+        .messages([message])
+        .build()?;
+
+    let mut stream = openai.chat().create_stream(request).await?;
+    let mut answer = String::new();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(chunk) => {
+                let content = chunk.choices.first().and_then(|c| c.delta.content.as_ref());
+                if let Some(content) = content {
+                    answer.push_str(content);
+                }
+            }
+            Err(OpenAIError::StreamError(e)) if e == "Stream ended" => break,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(answer)
+}
+
+/// Whether the answer states `expected`
+///
+/// Words with numbers copied from the question, like a week range, don't count, so echoing the
+/// question back can't pass. A word that is just the expected value still counts, since the
+/// answer can be a number the question also mentions.
+fn states_value(answer: &str, question: &str, expected: f64, tolerance: f64) -> bool {
+    let close = |n: f64| (n - expected).abs() <= tolerance;
+    let echoed: HashSet<&str> = words(question)
+        .filter(|word| word.contains(|c: char| c.is_ascii_digit()))
+        .collect();
+    words(answer)
+        .flat_map(|word| {
+            let values: Vec<f64> = numbers(word).collect();
+            let echo = echoed.contains(word) && !matches!(values[..], [n] if close(n));
+            if echo {
+                Vec::new()
+            } else {
+                values
+            }
+        })
+        .any(close)
+}
+
+/// Whitespace separated words without surrounding punctuation
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+}
+
+/// Numbers mentioned in text, ignoring thousands separators (e.g. "1,749 yards")
+fn numbers(text: &str) -> impl Iterator<Item = f64> + '_ {
+    text.split(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .map(|token| {
+            token
+                .trim_matches(|c| c == '.' || c == ',')
+                .replace(',', "")
+        })
+        .filter_map(|token| token.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUESTION: &str = "How many receiving yards did CeeDee Lamb have in 2023?";
+
+    #[test]
+    fn finds_the_expected_value() {
+        let answer = "CeeDee Lamb had 1,749 receiving yards in 2023.";
+        assert!(states_value(answer, QUESTION, 1749.0, 0.5));
+        assert!(!states_value(answer, QUESTION, 1700.0, 0.5));
+    }
+
+    #[test]
+    fn ignores_ranges_from_the_question() {
+        let question = "How many touchdowns did Derrick Henry score in weeks 4-8 of 2024?";
+        let answer = "I don't have stats for weeks 4-8 of 2024.";
+        assert!(!states_value(answer, question, 4.0, 0.5));
+        assert!(!states_value(answer, question, 8.0, 0.5));
+    }
+
+    #[test]
+    fn finds_expected_values_the_question_mentions() {
+        let question = "How many touchdowns did Derrick Henry score in weeks 4-8 of 2024?";
+        let answer = "Derrick Henry scored 4 touchdowns in weeks 4-8 of 2024.";
+        assert!(states_value(answer, question, 4.0, 0.5));
+
+        let question = "How many touchdowns did Derrick Henry score in week 4 of 2024?";
+        assert!(states_value("He scored 4.", question, 4.0, 0.5));
+    }
+}
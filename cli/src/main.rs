@@ -1,37 +1,123 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use arrow::util::pretty::print_batches;
-use clap::Parser;
-use fff::pbp::PbpQuery;
+use clap::{builder::PossibleValuesParser, Parser, Subcommand};
+use fff::pbp::{PbpQuery, STAT_COLUMNS};
+use fff::rankings::{League, Lineup};
+use fff::scoring::Scoring;
+use fff::teams::TeamQuery;
+use fff::tiers::{self, Projections};
+use fff::weeks::WeekFilter;
+use fff::years::Years;
 use futures::TryStreamExt;
 use itertools::Itertools;
-use spiceai::ClientBuilder;
+use spiceai::{Client, ClientBuilder};
+
+mod data;
+mod doctor;
+mod eval;
+mod rankings;
+mod spicepod;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
-    #[arg(short, long, action = clap::ArgAction::Count)]
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     verbose: u8,
 
     /// Loads data for a given year
-    #[arg(short = 'y', long = "year", default_value_t = 2024)]
+    #[arg(short = 'y', long = "year", default_value_t = 2024, global = true)]
     year: u16,
 
     /// Filter by team
-    #[arg(long = "player")]
+    #[arg(long = "player", global = true)]
     player: Option<String>,
 
     /// Filtering week number or range (e.g. 3 or 3-5)
-    #[arg(short = 'w', long = "week", alias = "weeks")]
+    #[arg(short = 'w', long = "week", alias = "weeks", global = true)]
     weeks: Option<WeekArg>,
 
     /// Filter by team
-    #[arg(short = 't', long = "team")]
+    #[arg(short = 't', long = "team", global = true)]
     team: Option<String>,
+
+    /// Adds fantasy points: standard, half_ppr, ppr or a TOML scoring file (e.g. league.toml)
+    #[arg(long = "scoring", value_parser = parse_scoring, global = true)]
+    scoring: Option<Scoring>,
     // TODO: position filtering requires cross-referencing roster info
     // https://github.com/nflverse/nflverse-data/releases/tag/weekly_rosters
     // Filter by position
     // #[arg(short = 'p', long = "pos", value_enum)]
     // position: Option<Position>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Rank players by season totals of a stat
+    Leaders {
+        /// Stat column to rank by (e.g. receiving_yards)
+        #[arg(short = 's', long = "stat", value_parser = PossibleValuesParser::new(STAT_COLUMNS))]
+        stat: String,
+
+        /// Filter by position
+        #[arg(short = 'p', long = "pos", alias = "position")]
+        position: Option<String>,
+
+        /// Number of players to list
+        #[arg(short = 'n', default_value_t = 24)]
+        n: u16,
+    },
+    /// Rank players by fantasy points over replacement for a league's lineup
+    Rankings {
+        /// Teams in the league
+        #[arg(long, default_value_t = 12, value_parser = clap::value_parser!(u16).range(1..))]
+        teams: u16,
+
+        /// Starting lineup slots, may include FLEX and SUPERFLEX
+        #[arg(long, default_value = "QB,RB,RB,WR,WR,TE,FLEX")]
+        lineup: Lineup,
+
+        /// Only list players at this position
+        #[arg(short = 'p', long = "pos", alias = "position")]
+        position: Option<String>,
+
+        /// Number of players to list
+        #[arg(short = 'n', default_value_t = 50)]
+        n: u16,
+
+        /// Tiers per position, split at the largest gaps in fantasy points
+        #[arg(long, default_value_t = tiers::DEFAULT_TIERS, value_parser = clap::value_parser!(u16).range(1..))]
+        tiers: u16,
+
+        /// CSV of `player_id,points` projections to tier on instead of season points
+        #[arg(long, value_parser = parse_projections)]
+        projections: Option<Projections>,
+    },
+    /// Summarize a team's weekly totals and player usage
+    Team {
+        /// Team abbreviation (e.g. KC)
+        #[arg(value_name = "TEAM")]
+        abbr: String,
+    },
+    /// Replay questions against a chat model and score its answers against computed stats
+    Eval(eval::EvalArgs),
+    /// Check each season's datasets have the columns and types the queries expect
+    Doctor {
+        /// Seasons to check, defaults to the API's configured seasons
+        #[arg(long, env = "YEARS", default_value = "2023-2024")]
+        years: Years,
+    },
+    /// Download nflverse data for offline use
+    Data {
+        #[command(subcommand)]
+        command: data::DataCommand,
+    },
+    /// Manage the Spice configuration
+    Spicepod {
+        #[command(subcommand)]
+        command: spicepod::SpicepodCommand,
+    },
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -53,16 +139,114 @@ impl std::str::FromStr for WeekArg {
     }
 }
 
+impl WeekArg {
+    /// Applies this week or week range as a filter on a query
+    fn filter<Q: WeekFilter>(self, query: Q) -> Q {
+        match self {
+            WeekArg::Week(week) => query.single_week(week),
+            WeekArg::WeekRange(start, end) => query.week_range(start, end),
+        }
+    }
+}
+
+fn parse_scoring(s: &str) -> Result<Scoring, String> {
+    if s.ends_with(".toml") {
+        Scoring::load(std::path::Path::new(s)).map_err(|err| err.to_string())
+    } else {
+        s.parse()
+    }
+}
+
+fn parse_projections(s: &str) -> Result<Projections, String> {
+    Projections::load(std::path::Path::new(s)).map_err(|err| err.to_string())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
 
+    if let Some(Command::Spicepod { command }) = args.command {
+        return spicepod::run(command);
+    }
+
+    if let Some(Command::Data { command }) = args.command {
+        return data::run(command).await;
+    }
+
     let mut client = ClientBuilder::new()
         .flight_url("http://localhost:50051")
         .build()
         .await
         .unwrap();
 
+    if let Some(Command::Doctor { years }) = args.command {
+        return doctor::run(&mut client, years, args.verbose).await;
+    }
+
+    if let Some(Command::Eval(eval_args)) = args.command {
+        return eval::run(&mut client, eval_args, args.verbose).await;
+    }
+
+    if let Some(Command::Team { abbr }) = &args.command {
+        if args.player.is_some() || args.team.is_some() {
+            bail!("--player and --team don't apply to `team`, pass the team as an argument");
+        }
+        let team = abbr.to_uppercase();
+        let mut games = TeamQuery::new(args.year).team(&team);
+        let mut players = PbpQuery::year(args.year)
+            .filter_team(&team)
+            .join_roster()
+            .usage_shares();
+        if let Some(weeks) = args.weeks {
+            games = weeks.filter(games);
+            players = weeks.filter(players);
+        }
+        if let Some(scoring) = &args.scoring {
+            players = players.fantasy_points(scoring);
+        }
+        run_query(&mut client, &games.sql(), args.verbose).await?;
+        return run_query(&mut client, &players.sql(), args.verbose).await;
+    }
+
+    if let Some(Command::Rankings {
+        teams,
+        lineup,
+        position,
+        n,
+        tiers,
+        projections,
+    }) = &args.command
+    {
+        let scoring = match &args.scoring {
+            Some(scoring) => scoring.clone(),
+            None => "ppr".parse().map_err(|err: String| anyhow!(err))?,
+        };
+        let mut query = PbpQuery::year(args.year)
+            .join_roster()
+            .aggregate()
+            .fantasy_points(&scoring);
+        if let Some(weeks) = args.weeks {
+            query = weeks.filter(query);
+        }
+        let league = League {
+            teams: *teams,
+            lineup: lineup.clone(),
+        };
+        return rankings::run(
+            &mut client,
+            &query.sql(),
+            &league,
+            position.as_deref(),
+            *n,
+            rankings::Tiers {
+                count: *tiers,
+                projections: projections.as_ref(),
+            },
+            args.verbose,
+        )
+        .await;
+    }
+
     let mut query_builder = PbpQuery::year(args.year);
 
     if let Some(player_name) = args.player {
@@ -73,23 +257,31 @@ async fn main() -> Result<()> {
         query_builder = query_builder.filter_team(&team);
     }
 
-    match args.weeks {
-        Some(WeekArg::Week(week)) => {
-            query_builder = query_builder.filter_week(week);
-        }
-        Some(WeekArg::WeekRange(start, end)) => {
-            query_builder = query_builder.filter_week_range(start, end);
+    if let Some(weeks) = args.weeks {
+        query_builder = weeks.filter(query_builder);
+    }
+
+    if let Some(scoring) = &args.scoring {
+        query_builder = query_builder.fantasy_points(scoring);
+    }
+
+    if let Some(Command::Leaders { stat, position, n }) = args.command {
+        if let Some(position) = position {
+            query_builder = query_builder.filter_position(&position);
         }
-        None => {}
+        query_builder = query_builder.rank_by(&stat)?.limit(n);
     }
 
-    let query = query_builder.sql();
-    if args.verbose > 0 {
+    run_query(&mut client, &query_builder.sql(), args.verbose).await
+}
+
+async fn run_query(client: &mut Client, query: &str, verbose: u8) -> Result<()> {
+    if verbose > 0 {
         println!("query: {query}");
     }
 
     let flight = client
-        .query(&query)
+        .query(query)
         .await
         .map_err(|err| anyhow!("Query error: {}", err))?;
 
@@ -0,0 +1,142 @@
+use anyhow::{anyhow, Context, Result};
+use arrow::array::{AsArray, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Float64Type};
+use fff::rankings::{self, League};
+use fff::tiers::{self, Projections};
+use futures::TryStreamExt;
+use spiceai::Client;
+
+/// One row of the season totals query
+struct Player {
+    id: String,
+    name: String,
+    team: String,
+    position: String,
+    points: f64,
+}
+
+/// How each position is split into tiers
+pub struct Tiers<'a> {
+    pub count: u16,
+    /// Projected points to tier on instead of season points, for the players they cover
+    pub projections: Option<&'a Projections>,
+}
+
+/// Ranks the players of a season totals query by value over replacement and prints them
+///
+/// Each position is also split into tiers at the largest gaps in points.
+pub async fn run(
+    spice: &mut Client,
+    query: &str,
+    league: &League,
+    position: Option<&str>,
+    n: u16,
+    tiers: Tiers<'_>,
+    verbose: u8,
+) -> Result<()> {
+    if verbose > 0 {
+        println!("query: {query}");
+    }
+
+    let batches: Vec<_> = spice
+        .query(query)
+        .await
+        .map_err(|err| anyhow!("Query error: {}", err))?
+        .try_collect()
+        .await?;
+    let mut players = Vec::new();
+    for batch in &batches {
+        players.extend(player_rows(batch)?);
+    }
+
+    let points: Vec<_> = players
+        .iter()
+        .map(|player| (player.position.as_str(), player.points))
+        .collect();
+    let rankings = rankings::rank(league, &points);
+    let projected: Vec<_> = match tiers.projections {
+        Some(projections) => players
+            .iter()
+            .map(|player| projections.get(&player.id))
+            .collect(),
+        None => Vec::new(),
+    };
+    let player_tiers = tiers::by_position(&points, &projected, &rankings.replacement, tiers.count);
+
+    let replacement = rankings
+        .replacement
+        .iter()
+        .map(|(position, points)| format!("{position} {points:.1}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    println!(
+        "{} teams, {}; replacement: {replacement}",
+        league.teams, league.lineup
+    );
+
+    let position = position.map(str::to_uppercase);
+    let mut ranked: Vec<_> = players
+        .iter()
+        .zip(rankings.players)
+        .zip(player_tiers)
+        .filter_map(|((player, ranking), tier)| Some((player, ranking?, tier.unwrap_or_default())))
+        .filter(|(player, _, _)| position.is_none() || position.as_ref() == Some(&player.position))
+        .collect();
+    ranked.sort_by_key(|(_, ranking, _)| ranking.overall_rank);
+
+    println!(
+        "{:>4}  {:<6}  {:>4}  {:<24}  {:<4}  {:>7}  {:>7}",
+        "rank", "pos", "tier", "player", "team", "points", "vorp"
+    );
+    for (player, ranking, tier) in ranked.into_iter().take(usize::from(n)) {
+        println!(
+            "{:>4}  {:<6}  {:>4}  {:<24}  {:<4}  {:>7.1}  {:>7.1}",
+            ranking.overall_rank,
+            ranking.pos_rank,
+            tier,
+            player.name,
+            player.team,
+            player.points,
+            ranking.vorp
+        );
+    }
+    Ok(())
+}
+
+/// Players of a season totals batch, with missing positions or points left empty
+fn player_rows(batch: &RecordBatch) -> Result<Vec<Player>> {
+    let column = |name: &str, data_type: DataType| {
+        let column = batch
+            .column_by_name(name)
+            .with_context(|| format!("missing column {name}"))?;
+        Ok::<_, anyhow::Error>(cast(column, &data_type)?)
+    };
+    let strings = |name: &str| -> Result<Vec<String>> {
+        Ok(column(name, DataType::Utf8)?
+            .as_string::<i32>()
+            .iter()
+            .map(|value| value.unwrap_or_default().to_string())
+            .collect())
+    };
+    let ids = strings("player_id")?;
+    let names = strings("player_name")?;
+    let teams = strings("team")?;
+    let positions = strings("position")?;
+    let points = column("fantasy_points", DataType::Float64)?;
+    let points = points.as_primitive::<Float64Type>();
+    Ok(ids
+        .into_iter()
+        .zip(names)
+        .zip(teams)
+        .zip(positions)
+        .zip(points.iter())
+        .map(|((((id, name), team), position), points)| Player {
+            id,
+            name,
+            team,
+            position,
+            points: points.unwrap_or_default(),
+        })
+        .collect())
+}
@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use fff::datasets::{Dataset, Source};
+use fff::years::Years;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Refresh interval for datasets that change during the current season
+const CURRENT_REFRESH: &str = "12h";
+/// Refresh interval for past seasons, which only see occasional corrections
+const PAST_REFRESH: &str = "30d";
+
+/// Models served by Spice, unchanged by generation
+const MODELS: &str = "\
+models:
+- from: openai:gpt-4o-mini
+  name: openai-with-spice
+  params:
+    spice_tools: auto
+    openai_api_key: ${ env:SPICE_OPENAI_API_KEY }
+    system_prompt: You are a data analyst processing NFL data.
+- from: openai:gpt-4o-mini
+  name: openai-with-fff
+  params:
+    # The API injects a system prompt built from the dataset schemas
+    openai_api_key: ${ env:SPICE_OPENAI_API_KEY }
+";
+
+#[derive(Subcommand, Debug)]
+pub enum SpicepodCommand {
+    /// Generate a spicepod.yaml with datasets for a range of seasons
+    Generate {
+        /// Season or range of seasons (e.g. 2015-2024)
+        #[arg(long, default_value = "2023-2024")]
+        years: Years,
+
+        /// Comma separated datasets: pbp, rosters, schedules, snaps
+        #[arg(long, value_delimiter = ',', default_value = "pbp,rosters")]
+        datasets: Vec<Dataset>,
+
+        /// Season that is still being played, defaults to the latest of `years`
+        #[arg(long)]
+        current: Option<u16>,
+
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+pub fn run(command: SpicepodCommand) -> Result<()> {
+    match command {
+        SpicepodCommand::Generate {
+            years,
+            datasets,
+            current,
+            output,
+        } => {
+            let yaml = generate(years, &datasets, current.unwrap_or(years.latest()));
+            match output {
+                Some(path) => std::fs::write(&path, yaml)
+                    .with_context(|| format!("writing {}", path.display())),
+                None => {
+                    print!("{yaml}");
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// Renders a spicepod with accelerated datasets, newest seasons first
+fn generate(years: Years, datasets: &[Dataset], current: u16) -> String {
+    let mut yaml = String::new();
+    let names = datasets.iter().map(Dataset::to_string).collect::<Vec<_>>();
+    let _ = writeln!(
+        yaml,
+        "# Generated by `melange spicepod generate --years {years} --datasets {}`",
+        names.join(",")
+    );
+    yaml.push_str("version: v1beta1\nkind: Spicepod\nname: spice_qs\ndatasets:\n");
+    for dataset in datasets {
+        for source in dataset.sources(years.iter().rev()) {
+            write_dataset(&mut yaml, &source, current);
+        }
+    }
+    yaml.push_str(MODELS);
+    yaml
+}
+
+fn write_dataset(yaml: &mut String, source: &Source, current: u16) {
+    // Datasets spanning all seasons include the current one
+    let refresh = match source.year {
+        Some(year) if year < current => PAST_REFRESH,
+        _ => CURRENT_REFRESH,
+    };
+    let _ = write!(
+        yaml,
+        "\
+- from: {url}
+  name: {name}
+  description: {description}
+  params:
+    file_format: {file_format}
+  acceleration:
+    enabled: true
+    refresh_check_interval: {refresh}
+    refresh_mode: full
+",
+        url = source.url,
+        name = source.name,
+        description = source.description,
+        file_format = source.file_format,
+    );
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+use std::str::FromStr;
+
+const NFLVERSE_RELEASES: &str = "https://github.com/nflverse/nflverse-data/releases/download";
+
+/// nflverse datasets that can be loaded into Spice
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Dataset {
+    /// Play-by-play, one file per season
+    Pbp,
+    /// Weekly rosters, one file per season
+    Rosters,
+    /// Game schedules and results, one file for all seasons
+    Schedules,
+    /// Offensive and defensive snap counts, one file per season
+    Snaps,
+}
+
+/// Where a dataset's data comes from, for one season or all of them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Source {
+    /// Spice dataset (table) name, e.g. `pbp2024`
+    pub name: String,
+    pub url: String,
+    pub description: String,
+    /// `parquet` or `csv`
+    pub file_format: &'static str,
+    /// Season, or `None` for datasets covering every season
+    pub year: Option<u16>,
+}
+
+impl Dataset {
+    pub const ALL: [Dataset; 4] = [
+        Dataset::Pbp,
+        Dataset::Rosters,
+        Dataset::Schedules,
+        Dataset::Snaps,
+    ];
+
+    /// Sources for this dataset covering `years`
+    pub fn sources(self, years: impl IntoIterator<Item = u16>) -> Vec<Source> {
+        let per_year = |name: &str, path: &str, description: &str| -> Vec<Source> {
+            years
+                .into_iter()
+                .map(|year| {
+                    let path = path.replace("{year}", &year.to_string());
+                    Source {
+                        name: format!("{name}{year}"),
+                        url: format!("{NFLVERSE_RELEASES}/{path}"),
+                        description: format!("NFL {description} {year}"),
+                        file_format: "parquet",
+                        year: Some(year),
+                    }
+                })
+                .collect()
+        };
+        match self {
+            Dataset::Pbp => per_year("pbp", "pbp/play_by_play_{year}.parquet", "Play-by-play"),
+            Dataset::Rosters => per_year(
+                "roster",
+                "weekly_rosters/roster_weekly_{year}.parquet",
+                "Weekly Rosters",
+            ),
+            Dataset::Snaps => per_year(
+                "snaps",
+                "snap_counts/snap_counts_{year}.parquet",
+                "Snap Counts",
+            ),
+            Dataset::Schedules => vec![Source {
+                name: "schedules".to_string(),
+                url: "https://raw.githubusercontent.com/nflverse/nfldata/master/data/games.csv"
+                    .to_string(),
+                description: "NFL Schedules and Results".to_string(),
+                file_format: "csv",
+                year: None,
+            }],
+        }
+    }
+}
+
+impl FromStr for Dataset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "pbp" => Ok(Dataset::Pbp),
+            "rosters" => Ok(Dataset::Rosters),
+            "schedules" => Ok(Dataset::Schedules),
+            "snaps" => Ok(Dataset::Snaps),
+            _ => Err(format!(
+                "unknown dataset: {s} (expected pbp, rosters, schedules or snaps)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Dataset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Dataset::Pbp => "pbp",
+            Dataset::Rosters => "rosters",
+            Dataset::Schedules => "schedules",
+            Dataset::Snaps => "snaps",
+        };
+        f.write_str(name)
+    }
+}
@@ -0,0 +1,93 @@
+use sql_query_builder as sql;
+
+use crate::safe_spice;
+
+/// One row per game with the teams and final score
+pub struct GameQuery {
+    query: sql::Select,
+}
+
+impl GameQuery {
+    pub fn new(year: u16) -> GameQuery {
+        let query = sql::Select::new()
+            .select(
+                "
+                game_id,
+                MIN(game_date) as game_date,
+                MIN(week) as week,
+                MIN(season_type) as season_type,
+                MIN(home_team) as home_team,
+                MIN(away_team) as away_team,
+                MAX(total_home_score) as home_score,
+                MAX(total_away_score) as away_score
+            ",
+            )
+            .from(&format!("pbp{year}"))
+            .group_by("game_id")
+            .order_by("game_date, game_id");
+
+        GameQuery { query }
+    }
+
+    /// Query to collect the scoring plays of a single game in order
+    pub fn scoring_plays(year: u16, game_id: &str) -> GameQuery {
+        let query = sql::Select::new()
+            .select(
+                r#"
+                play_id,
+                qtr,
+                time,
+                posteam as team,
+                play_type,
+                "desc",
+                total_home_score as home_score,
+                total_away_score as away_score
+            "#,
+            )
+            .from(&format!("pbp{year}"))
+            .where_clause(&format!("game_id = '{}'", safe_spice(game_id)))
+            .where_and("sp = 1")
+            .order_by("play_id");
+
+        GameQuery { query }
+    }
+
+    pub fn game(mut self, game_id: &str) -> Self {
+        self.query = self
+            .query
+            .where_and(&format!("game_id = '{}'", safe_spice(game_id)));
+        self
+    }
+
+    pub fn team(mut self, team: &str) -> Self {
+        let team = safe_spice(team);
+        self.query = self
+            .query
+            .where_and(&format!("(home_team = '{team}' OR away_team = '{team}')"));
+        self
+    }
+
+    pub fn single_week(mut self, week: u16) -> Self {
+        self.query = self.query.where_and(&format!("week = {week}"));
+        self
+    }
+
+    pub fn week_range(mut self, start: u16, end: u16) -> Self {
+        let clause = format!("week BETWEEN {start} AND {end}");
+        self.query = self.query.where_and(&clause);
+        self
+    }
+
+    pub fn query(self) -> sql::Select {
+        self.query
+    }
+
+    pub fn sql(self) -> String {
+        self.query.to_string()
+    }
+}
+
+/// Parses the season from an nflverse game id (e.g. `2024_01_BAL_KC`)
+pub fn season_from_game_id(game_id: &str) -> Option<u16> {
+    game_id.split('_').next()?.parse().ok()
+}
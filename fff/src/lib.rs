@@ -1,5 +1,15 @@
+pub mod datasets;
+pub mod games;
 pub mod pbp;
+pub mod plays;
+pub mod rankings;
 pub mod rosters;
+pub mod schema;
+pub mod scoring;
+pub mod teams;
+pub mod tiers;
+pub mod weeks;
+pub mod years;
 
 // This would NOT be acceptable to avoid SQL injection
 // but we don't really have a DB driver to hand parameterized queries to.
@@ -1,16 +1,55 @@
 use sql_query_builder as sql;
 
-use crate::{rosters::RosterQuery, safe_spice};
+use crate::{rosters::RosterQuery, safe_spice, scoring::Scoring};
 
 pub struct PbpQuery {
     passing: sql::Select,
     receiving: sql::Select,
     rushing: sql::Select,
     roster: RosterQuery,
-    limit: u16,
+    limit: Option<u16>,
     join_roster: bool,
+    aggregate: bool,
+    rank_by: Option<String>,
+    usage_shares: bool,
+    /// Per-game fantasy points expression, see [`Scoring::sql`]
+    fantasy_points: Option<String>,
 }
 
+/// Stat columns produced by [`PbpQuery`], both per-game and in aggregate
+pub const STAT_COLUMNS: &[&str] = &[
+    "passing_yards",
+    "pass_touchdowns",
+    "interceptions",
+    "passing_40yd_td",
+    "passing_50yd_td",
+    "targets",
+    "red_zone_targets",
+    "receptions",
+    "receiving_yards",
+    "receiving_touchdowns",
+    "receiving_2pt_conv",
+    "receiving_40yd_td",
+    "receiving_50yd_td",
+    "carries",
+    "red_zone_carries",
+    "rushing_yards",
+    "rush_touchdowns",
+    "rushing_2pt_conv",
+    "rushing_40yd_td",
+    "rushing_50yd_td",
+];
+
+/// Returns true if `stat` is one of the [`STAT_COLUMNS`]
+pub fn is_stat_column(stat: &str) -> bool {
+    STAT_COLUMNS.contains(&stat)
+}
+
+/// A stat name that is not one of the [`STAT_COLUMNS`]
+#[derive(Debug, thiserror::Error)]
+#[error("unknown stat: {0}")]
+pub struct UnknownStat(pub String);
+
 impl PbpQuery {
     /// Query to collect all the the passing stats
     pub fn passing(year: u16) -> sql::Select {
@@ -28,7 +67,8 @@ impl PbpQuery {
                 SUM(passing_yards) as passing_yards,
                 SUM(pass_touchdown) as pass_touchdowns,
                 SUM(interception) as interceptions,
-                SUM(CASE WHEN passing_yards > 50 THEN pass_touchdown ELSE 0 END) as passing_50yd_td
+                SUM(CASE WHEN passing_yards >= 40 THEN pass_touchdown ELSE 0 END) as passing_40yd_td,
+                SUM(CASE WHEN passing_yards >= 50 THEN pass_touchdown ELSE 0 END) as passing_50yd_td
             ",
             )
             .from(&format!("pbp{}", year))
@@ -47,11 +87,14 @@ impl PbpQuery {
                 posteam as team,
                 receiver_player_id as player_id,
                 receiver_player_name as player_name,
+                COUNT(*) as targets,
+                SUM(CASE WHEN yardline_100 <= 20 THEN 1 ELSE 0 END) as red_zone_targets,
                 SUM(complete_pass) as receptions,
                 SUM(receiving_yards) as receiving_yards,
                 SUM(pass_touchdown) as receiving_touchdowns,
                 SUM(CASE WHEN two_point_conv_result = 'success' THEN 1 ELSE 0 END) as receiving_2pt_conv,
-                SUM(CASE WHEN receiving_yards > 50 THEN pass_touchdown ELSE 0 END) as receiving_50yd_td
+                SUM(CASE WHEN receiving_yards >= 40 THEN pass_touchdown ELSE 0 END) as receiving_40yd_td,
+                SUM(CASE WHEN receiving_yards >= 50 THEN pass_touchdown ELSE 0 END) as receiving_50yd_td
             ")
             .from(&format!("pbp{}", year))
             .where_clause("receiver_player_name IS NOT NULL")
@@ -69,10 +112,13 @@ impl PbpQuery {
                 posteam as team,
                 rusher_player_id as player_id,
                 rusher_player_name as player_name,
+                SUM(rush_attempt) as carries,
+                SUM(CASE WHEN yardline_100 <= 20 THEN rush_attempt ELSE 0 END) as red_zone_carries,
                 SUM(rushing_yards) as rushing_yards,
                 SUM(rush_touchdown) as rush_touchdowns,
                 SUM(CASE WHEN two_point_conv_result = 'success' THEN 1 ELSE 0 END) as rushing_2pt_conv,
-                SUM(CASE WHEN rushing_yards > 50 THEN rush_touchdown ELSE 0 END) as rushing_50yd_td
+                SUM(CASE WHEN rushing_yards >= 40 THEN rush_touchdown ELSE 0 END) as rushing_40yd_td,
+                SUM(CASE WHEN rushing_yards >= 50 THEN rush_touchdown ELSE 0 END) as rushing_50yd_td
             ")
             .from(&format!("pbp{}", year))
             .where_clause("rusher_player_name IS NOT NULL")
@@ -89,8 +135,12 @@ impl PbpQuery {
             receiving,
             rushing,
             roster,
-            limit: 500,
+            limit: Some(500),
             join_roster: false,
+            aggregate: false,
+            rank_by: None,
+            usage_shares: false,
+            fantasy_points: None,
         }
     }
 
@@ -144,14 +194,14 @@ impl PbpQuery {
     pub fn filter_rusher_id(mut self, player_id: &str) -> Self {
         self.rushing = self
             .rushing
-            .where_and(&format!("receiver_player_id = '{}'", safe_spice(player_id)));
+            .where_and(&format!("rusher_player_id = '{}'", safe_spice(player_id)));
         self
     }
 
     pub fn filter_receiver_id(mut self, player_id: &str) -> Self {
         self.receiving = self
             .receiving
-            .where_and(&format!("rusher_player_id = '{}'", safe_spice(player_id)));
+            .where_and(&format!("receiver_player_id = '{}'", safe_spice(player_id)));
         self
     }
 
@@ -180,19 +230,77 @@ impl PbpQuery {
         self.where_and_each(&clause)
     }
 
+    pub fn filter_game(self, game_id: &str) -> Self {
+        let clause = format!("game_id = '{}'", safe_spice(game_id));
+        self.where_and_each(&clause)
+    }
+
     pub fn filter_team(mut self, team: &str) -> Self {
         self.roster = self.roster.team(team);
         let clause = format!("posteam = '{}'", safe_spice(team));
         self.where_and_each(&clause)
     }
 
+    /// Filters to plays against a defense, e.g. to see what a defense allows by position
+    pub fn filter_opponent(self, team: &str) -> Self {
+        let clause = format!("defteam = '{}'", safe_spice(team));
+        self.where_and_each(&clause)
+    }
+
     pub fn join_roster(mut self) -> Self {
         self.join_roster = true;
         self
     }
 
     pub fn limit(mut self, limit: u16) -> Self {
-        self.limit = limit;
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Removes the default 500 row limit, e.g. for bulk exports
+    pub fn unlimited(mut self) -> Self {
+        self.limit = None;
+        self
+    }
+
+    /// Sums stats per player across all matching games instead of returning per-game rows
+    pub fn aggregate(mut self) -> Self {
+        self.aggregate = true;
+        self
+    }
+
+    /// Ranks aggregated totals by a stat column, adding `rank` and `pos_rank` (e.g. WR12) columns
+    ///
+    /// Implies `aggregate` and `join_roster`. Players without the stat are excluded.
+    /// Fails if `stat` is not one of [`STAT_COLUMNS`].
+    pub fn rank_by(mut self, stat: &str) -> Result<Self, UnknownStat> {
+        if !is_stat_column(stat) {
+            return Err(UnknownStat(stat.to_string()));
+        }
+        self.rank_by = Some(stat.to_string());
+        self.aggregate = true;
+        self.join_roster = true;
+        Ok(self)
+    }
+
+    /// Adds each player's share of the matching targets, carries, and red zone touches
+    ///
+    /// Implies `aggregate`. Shares are relative to all rows matched, so this is
+    /// typically combined with `filter_team`.
+    pub fn usage_shares(mut self) -> Self {
+        self.usage_shares = true;
+        self.aggregate = true;
+        self
+    }
+
+    /// Adds `fantasy_points` under `scoring`, per game or summed when aggregated
+    ///
+    /// Implies `join_roster` if the scoring depends on position. Bonuses are awarded per game.
+    pub fn fantasy_points(mut self, scoring: &Scoring) -> Self {
+        if scoring.by_position() {
+            self.join_roster = true;
+        }
+        self.fantasy_points = Some(scoring.sql("pbpjoin", Some("rosters.position")));
         self
     }
 
@@ -212,15 +320,22 @@ impl PbpQuery {
                 p.passing_yards,
                 p.pass_touchdowns,
                 p.interceptions,
+                p.passing_40yd_td,
                 p.passing_50yd_td,
+                rx.targets,
+                rx.red_zone_targets,
                 rx.receptions,
                 rx.receiving_yards,
                 rx.receiving_touchdowns,
                 rx.receiving_2pt_conv,
+                rx.receiving_40yd_td,
                 rx.receiving_50yd_td,
+                r.carries,
+                r.red_zone_carries,
                 r.rushing_yards,
                 r.rush_touchdowns,
                 r.rushing_2pt_conv,
+                r.rushing_40yd_td,
                 r.rushing_50yd_td
             ",
             )
@@ -239,24 +354,74 @@ impl PbpQuery {
             .with("passing", self.passing)
             .with("receiving", self.receiving)
             .with("rushing", self.rushing)
-            .with("pbpjoin", pbp_join)
-            .select("*")
-            .from("pbpjoin")
-            .order_by("game_date, passing_yards DESC NULLS LAST, receiving_yards DESC NULLS LAST, rushing_yards DESC NULLS LAST");
+            .with("pbpjoin", pbp_join);
+
+        if self.aggregate {
+            join = join
+                .select("pbpjoin.player_id")
+                .select("MAX(pbpjoin.player_name) AS player_name")
+                .select("MAX(pbpjoin.team) AS team")
+                .select("COUNT(DISTINCT pbpjoin.game_id) AS games")
+                .from("pbpjoin")
+                .group_by("pbpjoin.player_id");
+            if self.join_roster {
+                join = join.select("MAX(rosters.position) AS position");
+            }
+            for stat in STAT_COLUMNS {
+                join = join.select(&format!("SUM(pbpjoin.{stat}) AS {stat}"));
+            }
+            if let Some(points) = &self.fantasy_points {
+                join = join.select(&format!("ROUND(SUM({points}), 2) AS fantasy_points"));
+            }
+            if self.usage_shares {
+                let share = |usage: &str, alias: &str| {
+                    format!("ROUND(CAST(SUM({usage}) AS DOUBLE) / NULLIF(SUM(SUM({usage})) OVER (), 0), 3) AS {alias}")
+                };
+                join = join
+                    .select(&share("pbpjoin.targets", "target_share"))
+                    .select(&share("pbpjoin.carries", "carry_share"))
+                    .select(&share(
+                        "COALESCE(pbpjoin.red_zone_targets, 0) + COALESCE(pbpjoin.red_zone_carries, 0)",
+                        "red_zone_share",
+                    ));
+            }
+            join = match &self.rank_by {
+                Some(stat) => join
+                    .select(&format!("RANK() OVER (ORDER BY SUM(pbpjoin.{stat}) DESC) AS rank"))
+                    .select(&format!("CONCAT(MAX(rosters.position), RANK() OVER (PARTITION BY MAX(rosters.position) ORDER BY SUM(pbpjoin.{stat}) DESC)) AS pos_rank"))
+                    .having(&format!("SUM(pbpjoin.{stat}) IS NOT NULL"))
+                    .order_by("rank, player_name"),
+                None if self.fantasy_points.is_some() => join.order_by("fantasy_points DESC NULLS LAST"),
+                None => join.order_by("passing_yards DESC NULLS LAST, receiving_yards DESC NULLS LAST, rushing_yards DESC NULLS LAST"),
+            };
+        } else {
+            join = join
+                .select("*")
+                .from("pbpjoin")
+                .order_by("game_date, passing_yards DESC NULLS LAST, receiving_yards DESC NULLS LAST, rushing_yards DESC NULLS LAST");
+            if let Some(points) = &self.fantasy_points {
+                join = join.select(&format!("ROUND({points}, 2) AS fantasy_points"));
+            }
+        }
 
         if self.join_roster {
             join = join
                 .with("rosters", self.roster.query())
-                // Calculating age was harder than expected
-                // Using cast to Int64 (seconds): https://github.com/apache/arrow-rs/blob/0a4d8a14b58e45ef92e31541f0b51a5b25de5f10/arrow-cast/src/cast/mod.rs#L275
-                // After v43, consider `extract(days from duration) / 365.25`: https://github.com/apache/datafusion/pull/12514
-                .select("ROUND(arrow_cast(pbpjoin.game_date::DATE - rosters.birth_date::DATE, 'Int64') / (60*60*24*365.25), 1) AS age")
                 .inner_join(
                     "rosters ON pbpjoin.player_id = rosters.id AND pbpjoin.week = rosters.week",
                 );
+            if !self.aggregate {
+                // Calculating age was harder than expected
+                // Using cast to Int64 (seconds): https://github.com/apache/arrow-rs/blob/0a4d8a14b58e45ef92e31541f0b51a5b25de5f10/arrow-cast/src/cast/mod.rs#L275
+                // After v43, consider `extract(days from duration) / 365.25`: https://github.com/apache/datafusion/pull/12514
+                join = join.select("ROUND(arrow_cast(pbpjoin.game_date::DATE - rosters.birth_date::DATE, 'Int64') / (60*60*24*365.25), 1) AS age");
+            }
         }
 
-        join.limit(&self.limit.to_string()).as_string()
+        if let Some(limit) = self.limit {
+            join = join.limit(&limit.to_string());
+        }
+        join.as_string()
     }
 }
 
@@ -0,0 +1,141 @@
+use sql_query_builder as sql;
+
+use crate::safe_spice;
+
+const PLAY_FIELDS: &str = r#"
+    game_id,
+    play_id,
+    game_date,
+    week,
+    qtr,
+    time,
+    posteam,
+    defteam,
+    down,
+    ydstogo,
+    yardline_100,
+    play_type,
+    "desc",
+    yards_gained,
+    epa,
+    touchdown,
+    passer_player_name,
+    receiver_player_name,
+    rusher_player_name
+"#;
+
+/// Player id columns, one for each role a player can have in a play
+const PLAYER_ROLE_COLUMNS: &[&str] = &[
+    "passer_player_id",
+    "receiver_player_id",
+    "rusher_player_id",
+    "lateral_receiver_player_id",
+    "lateral_rusher_player_id",
+    "interception_player_id",
+    "punt_returner_player_id",
+    "kickoff_returner_player_id",
+    "kicker_player_id",
+    "fumbled_1_player_id",
+    "fumbled_2_player_id",
+];
+
+/// Raw play-by-play rows with the key descriptive columns
+pub struct PlayQuery {
+    query: sql::Select,
+    limit: u16,
+    offset: u32,
+}
+
+impl PlayQuery {
+    pub fn new(year: u16) -> PlayQuery {
+        let query = sql::Select::new()
+            .select(PLAY_FIELDS)
+            .from(&format!("pbp{year}"))
+            .where_clause("play_type IS NOT NULL")
+            .order_by("game_date, game_id, play_id");
+
+        PlayQuery {
+            query,
+            limit: 100,
+            offset: 0,
+        }
+    }
+
+    /// Filter to plays involving a player in any role (passer, rusher, returner, etc.)
+    pub fn player_id(mut self, player_id: &str) -> Self {
+        let player_id = safe_spice(player_id);
+        let clause = PLAYER_ROLE_COLUMNS
+            .iter()
+            .map(|col| format!("{col} = '{player_id}'"))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        self.query = self.query.where_and(&format!("({clause})"));
+        self
+    }
+
+    pub fn game(mut self, game_id: &str) -> Self {
+        self.query = self
+            .query
+            .where_and(&format!("game_id = '{}'", safe_spice(game_id)));
+        self
+    }
+
+    pub fn team(mut self, team: &str) -> Self {
+        self.query = self
+            .query
+            .where_and(&format!("posteam = '{}'", safe_spice(team)));
+        self
+    }
+
+    pub fn play_type(mut self, play_type: &str) -> Self {
+        self.query = self.query.where_and(&format!(
+            "play_type = '{}'",
+            safe_spice(&play_type.to_lowercase())
+        ));
+        self
+    }
+
+    pub fn down(mut self, down: u8) -> Self {
+        self.query = self.query.where_and(&format!("down = {down}"));
+        self
+    }
+
+    /// Filter by yards to go for a first down (inclusive)
+    pub fn distance(mut self, min: Option<u16>, max: Option<u16>) -> Self {
+        if let Some(min) = min {
+            self.query = self.query.where_and(&format!("ydstogo >= {min}"));
+        }
+        if let Some(max) = max {
+            self.query = self.query.where_and(&format!("ydstogo <= {max}"));
+        }
+        self
+    }
+
+    pub fn single_week(mut self, week: u16) -> Self {
+        self.query = self.query.where_and(&format!("week = {week}"));
+        self
+    }
+
+    pub fn week_range(mut self, start: u16, end: u16) -> Self {
+        let clause = format!("week BETWEEN {start} AND {end}");
+        self.query = self.query.where_and(&clause);
+        self
+    }
+
+    pub fn limit(mut self, limit: u16) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn sql(self) -> String {
+        self.query
+            .limit(&self.limit.to_string())
+            .offset(&self.offset.to_string())
+            .as_string()
+    }
+}
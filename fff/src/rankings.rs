@@ -0,0 +1,304 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Positions that fill lineup slots, others (e.g. K, FB) aren't ranked
+pub const POSITIONS: [&str; 4] = ["QB", "RB", "WR", "TE"];
+
+/// A starting lineup slot
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Slot {
+    Qb,
+    Rb,
+    Wr,
+    Te,
+    /// RB, WR or TE
+    Flex,
+    /// QB, RB, WR or TE
+    Superflex,
+}
+
+impl Slot {
+    /// Whether a player at `position` can start in this slot
+    pub fn accepts(self, position: &str) -> bool {
+        match self {
+            Slot::Qb => position == "QB",
+            Slot::Rb => position == "RB",
+            Slot::Wr => position == "WR",
+            Slot::Te => position == "TE",
+            Slot::Flex => matches!(position, "RB" | "WR" | "TE"),
+            Slot::Superflex => POSITIONS.contains(&position),
+        }
+    }
+}
+
+impl FromStr for Slot {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "QB" => Ok(Slot::Qb),
+            "RB" => Ok(Slot::Rb),
+            "WR" => Ok(Slot::Wr),
+            "TE" => Ok(Slot::Te),
+            "FLEX" => Ok(Slot::Flex),
+            "SUPERFLEX" => Ok(Slot::Superflex),
+            _ => Err(format!(
+                "unknown lineup slot: {s} (expected QB, RB, WR, TE, FLEX or SUPERFLEX)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Slot::Qb => "QB",
+            Slot::Rb => "RB",
+            Slot::Wr => "WR",
+            Slot::Te => "TE",
+            Slot::Flex => "FLEX",
+            Slot::Superflex => "SUPERFLEX",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Starting lineup of one team, parsed from slots like `QB,RB,RB,WR,WR,TE,FLEX`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lineup(pub Vec<Slot>);
+
+impl Default for Lineup {
+    fn default() -> Self {
+        "QB,RB,RB,WR,WR,TE,FLEX".parse().unwrap()
+    }
+}
+
+impl FromStr for Lineup {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let slots = s
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<Slot>, _>>()?;
+        Ok(Lineup(slots))
+    }
+}
+
+impl fmt::Display for Lineup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, slot) in self.0.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(f, "{sep}{slot}")?;
+        }
+        Ok(())
+    }
+}
+
+/// League settings that determine how many players start at each position
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct League {
+    pub teams: u16,
+    pub lineup: Lineup,
+}
+
+impl Default for League {
+    fn default() -> Self {
+        League {
+            teams: 12,
+            lineup: Lineup::default(),
+        }
+    }
+}
+
+/// A player's value relative to the replacement level at their position
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ranking {
+    /// Points above the best player at the position who wouldn't start
+    pub vorp: f64,
+    /// Rank within the position by points, e.g. `RB12`
+    pub pos_rank: String,
+    /// Rank across positions by VORP
+    pub overall_rank: u32,
+}
+
+/// Rankings for a set of players, see [`rank`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rankings {
+    /// Points of the replacement-level player at each position
+    pub replacement: BTreeMap<String, f64>,
+    /// In the same order as the players given, `None` for positions that aren't ranked
+    pub players: Vec<Option<Ranking>>,
+}
+
+/// Ranks players by value over replacement from their `(position, points)`
+///
+/// Each team starts its dedicated slots with the best players at that position, then
+/// FLEX and SUPERFLEX slots are filled with the best players left. The replacement level
+/// at a position is the points of the best player there who doesn't start, or of the worst
+/// starter if they all do.
+pub fn rank(league: &League, players: &[(&str, f64)]) -> Rankings {
+    // Indexes of the players at each position, best first
+    let mut by_position: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, (position, _)) in players.iter().enumerate() {
+        if POSITIONS.contains(position) {
+            by_position.entry(*position).or_default().push(i);
+        }
+    }
+    for indexes in by_position.values_mut() {
+        indexes.sort_by(|a, b| players[*b].1.total_cmp(&players[*a].1));
+    }
+
+    // Dedicated slots first, so flex slots only take players left over
+    let teams = usize::from(league.teams);
+    let mut starters: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut slots = league.lineup.0.clone();
+    slots.sort();
+    for slot in slots {
+        for _ in 0..teams {
+            let best = by_position
+                .iter()
+                .filter(|(position, _)| slot.accepts(position))
+                .filter_map(|(position, indexes)| {
+                    let next = *indexes.get(starters.get(position).copied().unwrap_or(0))?;
+                    Some((*position, players[next].1))
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((position, _)) = best {
+                *starters.entry(position).or_default() += 1;
+            }
+        }
+    }
+
+    let replacement: BTreeMap<String, f64> = by_position
+        .iter()
+        .map(|(position, indexes)| {
+            // When every player at a position starts, the last of them is the replacement
+            let starting = starters.get(position).copied().unwrap_or(0);
+            let replacement = indexes.get(starting).or(indexes.last());
+            let points = replacement.map_or(0.0, |i| players[*i].1);
+            (position.to_string(), points)
+        })
+        .collect();
+
+    let mut rankings: Vec<Option<Ranking>> = vec![None; players.len()];
+    for (position, indexes) in &by_position {
+        for (rank, i) in indexes.iter().enumerate() {
+            rankings[*i] = Some(Ranking {
+                vorp: players[*i].1 - replacement[*position],
+                pos_rank: format!("{position}{}", rank + 1),
+                overall_rank: 0,
+            });
+        }
+    }
+
+    let mut overall: Vec<usize> = (0..players.len())
+        .filter(|i| rankings[*i].is_some())
+        .collect();
+    let vorp = |i: usize| rankings[i].as_ref().map_or(0.0, |ranking| ranking.vorp);
+    overall.sort_by(|a, b| vorp(*b).total_cmp(&vorp(*a)));
+    for (rank, i) in overall.into_iter().enumerate() {
+        if let Some(ranking) = &mut rankings[i] {
+            ranking.overall_rank = rank as u32 + 1;
+        }
+    }
+
+    Rankings {
+        replacement,
+        players: rankings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn league(teams: u16, lineup: &str) -> League {
+        League {
+            teams,
+            lineup: lineup.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn fills_flex_after_dedicated_slots() {
+        let players = [
+            ("RB", 200.0),
+            ("RB", 150.0),
+            ("RB", 120.0),
+            ("RB", 80.0),
+            ("WR", 180.0),
+            ("WR", 130.0),
+            ("WR", 100.0),
+            ("TE", 110.0),
+            ("TE", 90.0),
+        ];
+        let rankings = rank(&league(2, "RB,WR,FLEX"), &players);
+
+        // RB and WR slots take 2 each, then FLEX takes the best left: RB 120 and TE 110
+        assert_eq!(rankings.replacement["RB"], 80.0);
+        assert_eq!(rankings.replacement["WR"], 100.0);
+        assert_eq!(rankings.replacement["TE"], 90.0);
+
+        let ranking = rankings.players[2].as_ref().unwrap();
+        assert_eq!(ranking.vorp, 40.0);
+        assert_eq!(ranking.pos_rank, "RB3");
+    }
+
+    #[test]
+    fn superflex_takes_quarterbacks() {
+        let players = [
+            ("QB", 350.0),
+            ("QB", 300.0),
+            ("QB", 250.0),
+            ("QB", 200.0),
+            ("RB", 240.0),
+            ("RB", 180.0),
+        ];
+        let rankings = rank(&league(1, "QB,RB,SUPERFLEX"), &players);
+        assert_eq!(rankings.replacement["QB"], 250.0);
+        assert_eq!(rankings.replacement["RB"], 180.0);
+
+        let overall: Vec<_> = rankings
+            .players
+            .iter()
+            .map(|ranking| ranking.as_ref().unwrap().overall_rank)
+            .collect();
+        // SUPERFLEX takes QB 300 over RB 180, so RB 240 is worth more than QB 300
+        assert_eq!(overall, [1, 3, 4, 6, 2, 5]);
+    }
+
+    #[test]
+    fn replacement_is_last_starter_when_all_start() {
+        let players = [
+            ("QB", 400.0),
+            ("QB", 350.0),
+            ("QB", 300.0),
+            ("QB", 250.0),
+            ("RB", 200.0),
+            ("RB", 190.0),
+            ("RB", 150.0),
+            ("WR", 220.0),
+            ("WR", 210.0),
+            ("WR", 120.0),
+        ];
+        let rankings = rank(&league(2, "QB,RB,WR,FLEX,SUPERFLEX"), &players);
+
+        // All 4 QBs start, so the worst of them sets the level instead of zero
+        assert_eq!(rankings.replacement["QB"], 250.0);
+        let vorp: Vec<_> = rankings.players[..4]
+            .iter()
+            .map(|ranking| ranking.as_ref().unwrap().vorp)
+            .collect();
+        assert_eq!(vorp, [150.0, 100.0, 50.0, 0.0]);
+    }
+
+    #[test]
+    fn skips_unranked_positions() {
+        let rankings = rank(&League::default(), &[("K", 150.0), ("QB", 300.0)]);
+        assert_eq!(rankings.players[0], None);
+        assert_eq!(rankings.players[1].as_ref().unwrap().overall_rank, 1);
+        assert!(!rankings.replacement.contains_key("K"));
+    }
+}
@@ -34,6 +34,22 @@ impl RosterQuery {
         self
     }
 
+    pub fn player_id(mut self, player_id: &str) -> Self {
+        self.query = self
+            .query
+            .where_and(&format!("gsis_id = '{}'", safe_spice(player_id)));
+        self
+    }
+
+    /// Adds the player's current `age` in years
+    pub fn with_age(mut self) -> Self {
+        // See PbpQuery::sql for the arrow_cast workaround
+        self.query = self.query.select(
+            "ROUND(arrow_cast(NOW()::DATE - birth_date::DATE, 'Int64') / (60*60*24*365.25), 1) AS age",
+        );
+        self
+    }
+
     pub fn name_search(mut self, search: &str) -> Self {
         self.query = self
             .query
@@ -0,0 +1,263 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use ColumnType::{Any, Date, Number, Text};
+
+/// Broad type a query builder expects of a column, loose enough to tolerate
+/// nflverse switching between e.g. Int32 and Float64 across seasons
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    Text,
+    Number,
+    /// Date or timestamp, or text cast with `::DATE`
+    Date,
+    /// Only selected, so any type works
+    Any,
+}
+
+impl ColumnType {
+    /// Whether an `information_schema.columns` data type (e.g. `Float64`) is acceptable
+    pub fn accepts(self, data_type: &str) -> bool {
+        let text = data_type.contains("Utf8");
+        match self {
+            ColumnType::Text => text,
+            ColumnType::Number => ["Int", "UInt", "Float", "Decimal"]
+                .iter()
+                .any(|prefix| data_type.starts_with(prefix)),
+            ColumnType::Date => {
+                text || data_type.starts_with("Date") || data_type.starts_with("Timestamp")
+            }
+            ColumnType::Any => true,
+        }
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColumnType::Text => "text",
+            ColumnType::Number => "number",
+            ColumnType::Date => "date",
+            ColumnType::Any => "any",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A column referenced by the query builders
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Column {
+    pub name: &'static str,
+    pub ty: ColumnType,
+}
+
+const fn col(name: &'static str, ty: ColumnType) -> Column {
+    Column { name, ty }
+}
+
+/// Columns of `pbp{year}` used by [`PbpQuery`](crate::pbp::PbpQuery),
+/// [`GameQuery`](crate::games::GameQuery), [`PlayQuery`](crate::plays::PlayQuery)
+/// and [`TeamQuery`](crate::teams::TeamQuery)
+pub const PBP_COLUMNS: &[Column] = &[
+    col("game_id", Text),
+    col("play_id", Number),
+    col("game_date", Date),
+    col("week", Number),
+    col("season_type", Text),
+    col("home_team", Text),
+    col("away_team", Text),
+    col("posteam", Text),
+    col("defteam", Text),
+    col("qtr", Number),
+    col("time", Any),
+    col("down", Number),
+    col("ydstogo", Number),
+    col("yardline_100", Number),
+    col("play_type", Text),
+    col("desc", Text),
+    col("yards_gained", Number),
+    col("epa", Number),
+    col("touchdown", Number),
+    col("sp", Number),
+    col("total_home_score", Number),
+    col("total_away_score", Number),
+    col("passer_player_id", Text),
+    col("passer_player_name", Text),
+    col("receiver_player_id", Text),
+    col("receiver_player_name", Text),
+    col("rusher_player_id", Text),
+    col("rusher_player_name", Text),
+    col("lateral_receiver_player_id", Text),
+    col("lateral_rusher_player_id", Text),
+    col("interception_player_id", Text),
+    col("punt_returner_player_id", Text),
+    col("kickoff_returner_player_id", Text),
+    col("kicker_player_id", Text),
+    col("fumbled_1_player_id", Text),
+    col("fumbled_2_player_id", Text),
+    col("pass_attempt", Number),
+    col("complete_pass", Number),
+    col("passing_yards", Number),
+    col("pass_touchdown", Number),
+    col("interception", Number),
+    col("receiving_yards", Number),
+    col("rush_attempt", Number),
+    col("rushing_yards", Number),
+    col("rush_touchdown", Number),
+    col("two_point_conv_result", Text),
+];
+
+/// Columns of `roster{year}` used by [`RosterQuery`](crate::rosters::RosterQuery)
+pub const ROSTER_COLUMNS: &[Column] = &[
+    col("gsis_id", Text),
+    col("full_name", Text),
+    col("team", Text),
+    col("position", Text),
+    col("birth_date", Date),
+    col("height", Any),
+    col("weight", Any),
+    col("week", Number),
+    col("status", Text),
+];
+
+/// Datasets for a season and the columns expected of each
+pub fn tables(year: u16) -> [(String, &'static [Column]); 2] {
+    [
+        (format!("pbp{year}"), PBP_COLUMNS),
+        (format!("roster{year}"), ROSTER_COLUMNS),
+    ]
+}
+
+/// Query for the `table_name`, `column_name` and `data_type` of every dataset of `years`
+pub fn columns_query(years: impl IntoIterator<Item = u16>) -> String {
+    let names = years
+        .into_iter()
+        .flat_map(tables)
+        .map(|(table, _)| format!("'{table}'"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "SELECT table_name, column_name, data_type FROM information_schema.columns
+        WHERE table_name IN ({names})
+        ORDER BY table_name, ordinal_position"
+    )
+}
+
+/// A difference between the columns a builder expects and a dataset's schema
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Drift {
+    /// The dataset isn't loaded at all
+    MissingTable,
+    /// The column is gone, possibly renamed to a similarly named new column
+    Missing {
+        column: &'static str,
+        renamed_to: Option<String>,
+    },
+    WrongType {
+        column: &'static str,
+        expected: ColumnType,
+        found: String,
+    },
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Drift::MissingTable => write!(f, "dataset not found"),
+            Drift::Missing {
+                column,
+                renamed_to: Some(renamed),
+            } => write!(f, "missing column {column} (renamed to {renamed}?)"),
+            Drift::Missing {
+                column,
+                renamed_to: None,
+            } => write!(f, "missing column {column}"),
+            Drift::WrongType {
+                column,
+                expected,
+                found,
+            } => write!(f, "column {column} is {found}, expected {expected}"),
+        }
+    }
+}
+
+/// Compares a dataset's `(column_name, data_type)` pairs against the expected columns
+pub fn check(expected: &[Column], actual: &[(String, String)]) -> Vec<Drift> {
+    if actual.is_empty() {
+        return vec![Drift::MissingTable];
+    }
+
+    let known: HashSet<_> = expected.iter().map(|column| column.name).collect();
+    // Columns we don't reference are the candidates for renames
+    let unknown: Vec<_> = actual
+        .iter()
+        .filter(|(name, _)| !known.contains(name.as_str()))
+        .collect();
+
+    let mut drift = Vec::new();
+    for column in expected {
+        match actual.iter().find(|(name, _)| name == column.name) {
+            Some((_, data_type)) if !column.ty.accepts(data_type) => drift.push(Drift::WrongType {
+                column: column.name,
+                expected: column.ty,
+                found: data_type.clone(),
+            }),
+            Some(_) => {}
+            None => {
+                let renamed_to = unknown
+                    .iter()
+                    .filter(|(_, data_type)| column.ty.accepts(data_type))
+                    .map(|(name, _)| (edit_distance(column.name, name), name))
+                    .filter(|(distance, name)| {
+                        *distance <= 2.max(column.name.len() / 4) || same_words(column.name, name)
+                    })
+                    .min_by_key(|(distance, _)| *distance)
+                    .map(|(_, name)| name.clone());
+                drift.push(Drift::Missing {
+                    column: column.name,
+                    renamed_to,
+                });
+            }
+        }
+    }
+    drift
+}
+
+/// Checks each dataset of `year` against the `(table_name, column_name, data_type)`
+/// rows returned by [`columns_query`]
+pub fn check_year(year: u16, rows: &[(String, String, String)]) -> Vec<(String, Drift)> {
+    let mut drift = Vec::new();
+    for (table, expected) in tables(year) {
+        let actual: Vec<_> = rows
+            .iter()
+            .filter(|(table_name, _, _)| *table_name == table)
+            .map(|(_, column, data_type)| (column.clone(), data_type.clone()))
+            .collect();
+        for found in check(expected, &actual) {
+            drift.push((table.clone(), found));
+        }
+    }
+    drift
+}
+
+/// Whether every `_` separated word of one name appears in the other, e.g. `passer_id`
+/// and `passer_player_id`
+fn same_words(a: &str, b: &str) -> bool {
+    let (a, b): (HashSet<_>, HashSet<_>) = (a.split('_').collect(), b.split('_').collect());
+    a.is_subset(&b) || b.is_subset(&a)
+}
+
+/// Levenshtein distance, used to guess which new column replaced a missing one
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            row.push(substitute.min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
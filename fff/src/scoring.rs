@@ -0,0 +1,560 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::pbp::STAT_COLUMNS;
+use crate::safe_spice;
+
+/// Fantasy points awarded per unit of each [`STAT_COLUMNS`] stat
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scoring {
+    pub name: String,
+    /// Points per unit of a stat, stats that don't score are omitted
+    pub points: BTreeMap<String, f64>,
+    /// Per-position points replacing those in `points`, e.g. a TE premium on receptions
+    pub positions: BTreeMap<String, BTreeMap<String, f64>>,
+    /// Extra points for reaching a stat threshold in a single game
+    pub bonuses: Vec<Bonus>,
+}
+
+/// Points for reaching a threshold of a stat in one game, e.g. +3 for 100 rushing yards
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bonus {
+    pub stat: String,
+    /// Highest threshold first, only the highest tier reached is awarded
+    pub tiers: Vec<Tier>,
+    /// Positions the bonus applies to, all of them if empty
+    pub positions: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tier {
+    pub at_least: f64,
+    pub points: f64,
+}
+
+/// A bonus as written in a config, either a single `at_least` threshold or `tiers`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BonusFile {
+    stat: String,
+    at_least: Option<f64>,
+    points: Option<f64>,
+    #[serde(default)]
+    tiers: Vec<Tier>,
+    #[serde(default)]
+    positions: Vec<String>,
+}
+
+/// Scoring config file, e.g.
+///
+/// ```toml
+/// name = "dynasty"
+/// # Preset to start from, `points` below replace its values
+/// preset = "ppr"
+///
+/// [points]
+/// pass_touchdowns = 6
+/// interceptions = -4
+///
+/// [positions.TE]
+/// receptions = 1.5
+///
+/// [[bonuses]]
+/// stat = "passing_yards"
+/// at_least = 300
+/// points = 3
+/// positions = ["QB"]
+///
+/// # Tiers award the points of the highest threshold reached, not each one
+/// [[bonuses]]
+/// stat = "rushing_yards"
+/// tiers = [{ at_least = 100, points = 3 }, { at_least = 200, points = 6 }]
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScoringFile {
+    name: Option<String>,
+    preset: Option<String>,
+    #[serde(default)]
+    points: BTreeMap<String, f64>,
+    #[serde(default)]
+    positions: BTreeMap<String, BTreeMap<String, f64>>,
+    #[serde(default)]
+    bonuses: Vec<BonusFile>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScoringError {
+    #[error("reading {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+
+    #[error("{0}")]
+    Preset(String),
+
+    #[error("unknown stat `{stat}` in {location}, expected one of: {}", STAT_COLUMNS.join(", "))]
+    UnknownStat { stat: String, location: String },
+
+    #[error("invalid bonus for {stat}: {reason}")]
+    InvalidBonus { stat: String, reason: &'static str },
+
+    #[error("points for `{stat}` in {location} must be a finite number")]
+    NotFinite { stat: String, location: String },
+}
+
+impl Scoring {
+    /// Standard (non-PPR) scoring
+    pub fn standard() -> Self {
+        Self::with_receptions("standard", 0.0)
+    }
+
+    pub fn half_ppr() -> Self {
+        Self::with_receptions("half_ppr", 0.5)
+    }
+
+    pub fn ppr() -> Self {
+        Self::with_receptions("ppr", 1.0)
+    }
+
+    fn with_receptions(name: &str, per_reception: f64) -> Self {
+        let points = [
+            ("passing_yards", 0.04),
+            ("pass_touchdowns", 4.0),
+            ("interceptions", -2.0),
+            ("receptions", per_reception),
+            ("receiving_yards", 0.1),
+            ("receiving_touchdowns", 6.0),
+            ("receiving_2pt_conv", 2.0),
+            ("rushing_yards", 0.1),
+            ("rush_touchdowns", 6.0),
+            ("rushing_2pt_conv", 2.0),
+        ]
+        .into_iter()
+        .filter(|(_, points)| *points != 0.0)
+        .map(|(stat, points)| (stat.to_string(), points))
+        .collect();
+        Scoring {
+            name: name.to_string(),
+            points,
+            positions: BTreeMap::new(),
+            bonuses: Vec::new(),
+        }
+    }
+
+    /// Parses a TOML scoring config, rejecting unknown keys and stats
+    ///
+    /// `default_name` is used if the config doesn't set a `name`.
+    pub fn from_toml(toml: &str, default_name: &str) -> Result<Self, ScoringError> {
+        let file: ScoringFile = toml::from_str(toml)?;
+        let mut scoring = match &file.preset {
+            Some(preset) => preset.parse().map_err(ScoringError::Preset)?,
+            None => Scoring {
+                name: String::new(),
+                points: BTreeMap::new(),
+                positions: BTreeMap::new(),
+                bonuses: Vec::new(),
+            },
+        };
+        scoring.name = file.name.unwrap_or_else(|| default_name.to_string());
+
+        check_stats(&file.points, "[points]")?;
+        scoring.points.extend(file.points);
+        for (position, points) in file.positions {
+            check_stats(&points, &format!("[positions.{position}]"))?;
+            scoring.positions.insert(position.to_uppercase(), points);
+        }
+        for bonus in file.bonuses {
+            check_stat(&bonus.stat, "[[bonuses]]")?;
+            scoring.bonuses.push(bonus.try_into()?);
+        }
+        Ok(scoring)
+    }
+
+    /// Loads a TOML scoring config, named after the file unless it sets a `name`
+    pub fn load(path: &Path) -> Result<Self, ScoringError> {
+        let toml = std::fs::read_to_string(path).map_err(|source| ScoringError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        Self::from_toml(&toml, &stem)
+    }
+
+    /// Points per unit of `stat`, zero for stats that don't score
+    pub fn points(&self, stat: &str) -> f64 {
+        debug_assert!(STAT_COLUMNS.contains(&stat), "unknown stat column: {stat}");
+        self.points.get(stat).copied().unwrap_or_default()
+    }
+
+    /// Whether scoring depends on a player's position, so rows need roster info
+    pub fn by_position(&self) -> bool {
+        !self.positions.is_empty() || self.bonuses.iter().any(|bonus| !bonus.positions.is_empty())
+    }
+
+    /// SQL expression for the fantasy points of one per-game row
+    ///
+    /// Stat columns are read from `row` (e.g. `pbpjoin`), and `position` is the column
+    /// holding the player's position, which is required if [`Self::by_position`].
+    pub fn sql(&self, row: &str, position: Option<&str>) -> String {
+        let position = position.filter(|_| self.by_position());
+        let mut terms = Vec::new();
+        for stat in STAT_COLUMNS {
+            let value = format!("COALESCE({row}.{stat}, 0)");
+            let base = self.points(stat);
+            let overrides: Vec<_> = self
+                .positions
+                .iter()
+                .filter_map(|(pos, points)| Some((pos, points.get(*stat)?)))
+                .collect();
+            match position {
+                Some(position) if !overrides.is_empty() => {
+                    let cases = overrides
+                        .iter()
+                        .map(|(pos, points)| format!("WHEN '{}' THEN {points:?}", safe_spice(pos)))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    terms.push(format!(
+                        "{value} * CASE {position} {cases} ELSE {base:?} END"
+                    ));
+                }
+                _ if base != 0.0 => terms.push(format!("{value} * {base:?}")),
+                _ => {}
+            }
+        }
+
+        // Rows are per game, so thresholds apply to single games even when summed later
+        for bonus in &self.bonuses {
+            let eligible = match position {
+                Some(position) if !bonus.positions.is_empty() => {
+                    let positions = bonus
+                        .positions
+                        .iter()
+                        .map(|pos| format!("'{}'", safe_spice(pos)))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    format!(" AND {position} IN ({positions})")
+                }
+                // Without positions, position-specific bonuses can't be awarded
+                None if !bonus.positions.is_empty() => continue,
+                _ => String::new(),
+            };
+            // Tiers are highest first, so the first match is the highest tier reached
+            let cases = bonus
+                .tiers
+                .iter()
+                .map(|tier| {
+                    format!(
+                        "WHEN COALESCE({row}.{}, 0) >= {:?}{eligible} THEN {:?}",
+                        bonus.stat, tier.at_least, tier.points
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            terms.push(format!("CASE {cases} ELSE 0 END"));
+        }
+
+        if terms.is_empty() {
+            "0.0".to_string()
+        } else {
+            terms.join(" + ")
+        }
+    }
+}
+
+impl TryFrom<BonusFile> for Bonus {
+    type Error = ScoringError;
+
+    fn try_from(file: BonusFile) -> Result<Self, Self::Error> {
+        let invalid = |reason| ScoringError::InvalidBonus {
+            stat: file.stat.clone(),
+            reason,
+        };
+        let mut tiers = match (file.at_least, file.points, file.tiers.is_empty()) {
+            (Some(at_least), Some(points), true) => vec![Tier { at_least, points }],
+            (None, None, false) => file.tiers.clone(),
+            (_, _, false) => return Err(invalid("use either `at_least` and `points` or `tiers`")),
+            _ => return Err(invalid("`at_least` and `points` are both required")),
+        };
+        if tiers
+            .iter()
+            .any(|tier| !tier.at_least.is_finite() || !tier.points.is_finite())
+        {
+            return Err(invalid("thresholds and points must be finite numbers"));
+        }
+        tiers.sort_by(|a, b| b.at_least.total_cmp(&a.at_least));
+        if tiers
+            .windows(2)
+            .any(|pair| pair[0].at_least == pair[1].at_least)
+        {
+            return Err(invalid("tiers must have different thresholds"));
+        }
+        Ok(Bonus {
+            stat: file.stat,
+            tiers,
+            positions: file
+                .positions
+                .iter()
+                .map(|position| position.to_uppercase())
+                .collect(),
+        })
+    }
+}
+
+fn check_stat(stat: &str, location: &str) -> Result<(), ScoringError> {
+    if !STAT_COLUMNS.contains(&stat) {
+        return Err(ScoringError::UnknownStat {
+            stat: stat.to_string(),
+            location: location.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Checks each stat is known and its points are finite, NaN or inf would only fail once
+/// formatted into a query
+fn check_stats(points: &BTreeMap<String, f64>, location: &str) -> Result<(), ScoringError> {
+    for (stat, points) in points {
+        check_stat(stat, location)?;
+        if !points.is_finite() {
+            return Err(ScoringError::NotFinite {
+                stat: stat.clone(),
+                location: location.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Scoring::ppr()
+    }
+}
+
+impl FromStr for Scoring {
+    type Err = String;
+
+    /// Parses a preset name: `standard`, `half_ppr` or `ppr`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(Scoring::standard()),
+            "half_ppr" => Ok(Scoring::half_ppr()),
+            "ppr" => Ok(Scoring::ppr()),
+            _ => Err(format!(
+                "unknown scoring: {s} (expected standard, half_ppr or ppr)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Scoring {
+    /// Lists points per stat, overrides and bonuses, e.g.
+    /// `ppr: 1 per receptions, 0.1 per receiving_yards; TE: 1.5 per receptions`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.name)?;
+        for (i, (stat, points)) in self.points.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            write!(f, "{sep}{points} per {stat}")?;
+        }
+        for (position, points) in &self.positions {
+            write!(f, "; {position}:")?;
+            for (i, (stat, points)) in points.iter().enumerate() {
+                let sep = if i == 0 { " " } else { ", " };
+                write!(f, "{sep}{points} per {stat}")?;
+            }
+        }
+        for bonus in &self.bonuses {
+            // Lowest tier first reads naturally, e.g. `+3/+6 for 100/200 rushing_yards`
+            let tiers = bonus.tiers.iter().rev();
+            let points = tiers.clone().map(|tier| format!("{:+}", tier.points));
+            let thresholds = tiers.map(|tier| tier.at_least.to_string());
+            write!(
+                f,
+                "; {} for {} {} in a game",
+                points.collect::<Vec<_>>().join("/"),
+                thresholds.collect::<Vec<_>>().join("/"),
+                bonus.stat
+            )?;
+            if !bonus.positions.is_empty() {
+                write!(f, " ({})", bonus.positions.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    /// Per-game fixture rows of `(player_id, position, [(stat, value)])`
+    type Game<'a> = (&'a str, &'a str, &'a [(&'a str, f64)]);
+
+    /// Season fantasy points per player, summing `scoring` over per-game rows like `PbpQuery`
+    fn season_points(scoring: &Scoring, games: &[Game]) -> BTreeMap<String, f64> {
+        let db = Connection::open_in_memory().unwrap();
+        let columns: Vec<_> = STAT_COLUMNS
+            .iter()
+            .map(|stat| format!("{stat} REAL"))
+            .collect();
+        db.execute(
+            &format!(
+                "CREATE TABLE pbpjoin (player_id TEXT, position TEXT, {})",
+                columns.join(", ")
+            ),
+            (),
+        )
+        .unwrap();
+        for (player_id, position, stats) in games {
+            let names: Vec<_> = stats.iter().map(|(stat, _)| *stat).collect();
+            let values: Vec<_> = stats.iter().map(|(_, value)| value.to_string()).collect();
+            db.execute(
+                &format!(
+                    "INSERT INTO pbpjoin (player_id, position, {}) VALUES ('{player_id}', '{position}', {})",
+                    names.join(", "),
+                    values.join(", ")
+                ),
+                (),
+            )
+            .unwrap();
+        }
+
+        let points = scoring.sql("pbpjoin", Some("pbpjoin.position"));
+        let mut query = db
+            .prepare(&format!(
+                "SELECT player_id, ROUND(SUM({points}), 2) FROM pbpjoin GROUP BY player_id"
+            ))
+            .unwrap();
+        query
+            .query_map((), |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    fn config(toml: &str) -> Scoring {
+        Scoring::from_toml(toml, "league").unwrap()
+    }
+
+    #[test]
+    fn awards_only_the_highest_tier() {
+        let scoring = config(
+            r#"
+            [[bonuses]]
+            stat = "rushing_yards"
+            tiers = [{ at_least = 100, points = 3 }, { at_least = 200, points = 6 }]
+            "#,
+        );
+        let points = season_points(
+            &scoring,
+            &[
+                ("short", "RB", &[("rushing_yards", 99.0)]),
+                ("century", "RB", &[("rushing_yards", 100.0)]),
+                ("double", "RB", &[("rushing_yards", 210.0)]),
+            ],
+        );
+        assert_eq!(points["short"], 0.0);
+        assert_eq!(points["century"], 3.0);
+        assert_eq!(points["double"], 6.0);
+    }
+
+    #[test]
+    fn thresholds_apply_per_game() {
+        let scoring = config(
+            r#"
+            [points]
+            rushing_yards = 0.1
+
+            [[bonuses]]
+            stat = "rushing_yards"
+            at_least = 100
+            points = 3
+            "#,
+        );
+        let points = season_points(
+            &scoring,
+            &[
+                // 120 yards over the season, but never 100 in one game
+                ("steady", "RB", &[("rushing_yards", 60.0)]),
+                ("steady", "RB", &[("rushing_yards", 60.0)]),
+                ("boom", "RB", &[("rushing_yards", 110.0)]),
+                ("boom", "RB", &[("rushing_yards", 10.0)]),
+            ],
+        );
+        assert_eq!(points["steady"], 12.0);
+        assert_eq!(points["boom"], 15.0);
+    }
+
+    #[test]
+    fn restricts_bonuses_and_overrides_to_positions() {
+        let scoring = config(
+            r#"
+            [points]
+            receptions = 1
+
+            [positions.TE]
+            receptions = 1.5
+
+            [[bonuses]]
+            stat = "passing_yards"
+            at_least = 300
+            points = 3
+            positions = ["QB"]
+            "#,
+        );
+        let points = season_points(
+            &scoring,
+            &[
+                ("qb", "QB", &[("passing_yards", 320.0)]),
+                // Trick plays don't earn the QB bonus
+                ("wr", "WR", &[("passing_yards", 320.0), ("receptions", 4.0)]),
+                ("te", "TE", &[("receptions", 4.0)]),
+            ],
+        );
+        assert_eq!(points["qb"], 3.0);
+        assert_eq!(points["wr"], 4.0);
+        assert_eq!(points["te"], 6.0);
+    }
+
+    #[test]
+    fn skips_position_bonuses_without_positions() {
+        let scoring = config(
+            r#"
+            [[bonuses]]
+            stat = "passing_yards"
+            at_least = 300
+            points = 3
+            positions = ["QB"]
+            "#,
+        );
+        assert_eq!(scoring.sql("pbpjoin", None), "0.0");
+    }
+
+    #[test]
+    fn rejects_non_finite_points() {
+        let err = Scoring::from_toml("[points]\nreceptions = inf", "league").unwrap_err();
+        assert!(matches!(err, ScoringError::NotFinite { .. }), "{err}");
+
+        let err = Scoring::from_toml("[positions.TE]\nreceptions = nan", "league").unwrap_err();
+        assert!(matches!(err, ScoringError::NotFinite { .. }), "{err}");
+
+        let toml = "[[bonuses]]\nstat = \"rushing_yards\"\nat_least = nan\npoints = 3";
+        let err = Scoring::from_toml(toml, "league").unwrap_err();
+        assert!(matches!(err, ScoringError::InvalidBonus { .. }), "{err}");
+
+        let toml =
+            "[[bonuses]]\nstat = \"rushing_yards\"\ntiers = [{ at_least = 100, points = -inf }]";
+        let err = Scoring::from_toml(toml, "league").unwrap_err();
+        assert!(matches!(err, ScoringError::InvalidBonus { .. }), "{err}");
+    }
+}
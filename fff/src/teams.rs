@@ -0,0 +1,63 @@
+use sql_query_builder as sql;
+
+use crate::safe_spice;
+
+/// Team-level offensive totals per game
+///
+/// Pace is reported as `plays`, the number of offensive pass and run plays.
+pub struct TeamQuery {
+    query: sql::Select,
+}
+
+impl TeamQuery {
+    pub fn new(year: u16) -> TeamQuery {
+        let query = sql::Select::new()
+            .select(
+                "
+                game_id,
+                MIN(game_date) as game_date,
+                MIN(week) as week,
+                posteam as team,
+                MIN(defteam) as opponent,
+                SUM(pass_attempt) as pass_attempts,
+                SUM(rush_attempt) as rush_attempts,
+                SUM(passing_yards) as passing_yards,
+                SUM(rushing_yards) as rushing_yards,
+                MAX(CASE WHEN posteam = home_team THEN total_home_score ELSE total_away_score END) as points,
+                SUM(CASE WHEN play_type IN ('pass', 'run') THEN 1 ELSE 0 END) as plays
+            ",
+            )
+            .from(&format!("pbp{year}"))
+            .where_clause("posteam IS NOT NULL")
+            .group_by("game_id, posteam")
+            .order_by("week");
+
+        TeamQuery { query }
+    }
+
+    pub fn team(mut self, team: &str) -> Self {
+        self.query = self
+            .query
+            .where_and(&format!("posteam = '{}'", safe_spice(team)));
+        self
+    }
+
+    pub fn single_week(mut self, week: u16) -> Self {
+        self.query = self.query.where_and(&format!("week = {week}"));
+        self
+    }
+
+    pub fn week_range(mut self, start: u16, end: u16) -> Self {
+        let clause = format!("week BETWEEN {start} AND {end}");
+        self.query = self.query.where_and(&clause);
+        self
+    }
+
+    pub fn query(self) -> sql::Select {
+        self.query
+    }
+
+    pub fn sql(self) -> String {
+        self.query.to_string()
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Tiers per position when a league doesn't choose
+pub const DEFAULT_TIERS: u16 = 6;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProjectionsError {
+    #[error("reading {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("line {line}: {reason}")]
+    Invalid { line: usize, reason: String },
+}
+
+/// Projected fantasy points by player id, to tier on instead of season points
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Projections(BTreeMap<String, f64>);
+
+impl Projections {
+    /// Parses `player_id,points` lines, skipping blank lines and an optional header
+    pub fn from_csv(csv: &str) -> Result<Self, ProjectionsError> {
+        let mut points = BTreeMap::new();
+        for (i, line) in csv.lines().enumerate() {
+            let invalid = |reason: &str| ProjectionsError::Invalid {
+                line: i + 1,
+                reason: reason.to_string(),
+            };
+            let line = line.trim();
+            if line.is_empty() || (i == 0 && line.starts_with("player_id")) {
+                continue;
+            }
+            let (player_id, value) = line
+                .split_once(',')
+                .ok_or_else(|| invalid("expected player_id,points"))?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .map_err(|_| invalid("points must be a number"))?;
+            if !value.is_finite() {
+                return Err(invalid("points must be a finite number"));
+            }
+            points.insert(player_id.trim().to_string(), value);
+        }
+        Ok(Projections(points))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ProjectionsError> {
+        let csv = std::fs::read_to_string(path).map_err(|source| ProjectionsError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Self::from_csv(&csv)
+    }
+
+    /// Projected points of a player, `None` if they weren't projected
+    pub fn get(&self, player_id: &str) -> Option<f64> {
+        self.0.get(player_id).copied()
+    }
+}
+
+/// Splits players into `count` tiers at the largest gaps between their points
+///
+/// Returns the tier of each player in the order given, 1 being the best. Tied players are never
+/// split, so there are fewer tiers when there aren't enough distinct scores.
+pub fn split(points: &[f64], count: u16) -> Vec<u32> {
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|a, b| points[*b].total_cmp(&points[*a]));
+
+    // A cut at `i` starts a new tier with the i-th best player, widest gaps first
+    let gap = |i: usize| points[order[i - 1]] - points[order[i]];
+    let mut cuts: Vec<usize> = (1..order.len()).filter(|i| gap(*i) > 0.0).collect();
+    cuts.sort_by(|a, b| gap(*b).total_cmp(&gap(*a)));
+    cuts.truncate(usize::from(count.saturating_sub(1)));
+    cuts.sort();
+
+    let mut tiers = vec![0; points.len()];
+    let mut tier = 1;
+    let mut cuts = cuts.into_iter().peekable();
+    for (rank, i) in order.into_iter().enumerate() {
+        if cuts.next_if_eq(&rank).is_some() {
+            tier += 1;
+        }
+        tiers[i] = tier;
+    }
+    tiers
+}
+
+/// Tiers players within each position from their `(position, points)`
+///
+/// `projected` is indexed like `players`, a player's projected points are used in place of their
+/// season points when given. Only players at or above a position's `replacement` level are split
+/// by gaps, everyone below is in the last tier, so a long tail of bench players doesn't swallow
+/// the tiers. `None` for positions without a replacement level.
+pub fn by_position(
+    players: &[(&str, f64)],
+    projected: &[Option<f64>],
+    replacement: &BTreeMap<String, f64>,
+    count: u16,
+) -> Vec<Option<u32>> {
+    let points = |i: usize| projected.get(i).copied().flatten().unwrap_or(players[i].1);
+    let mut tiers = vec![None; players.len()];
+    for (position, replacement) in replacement {
+        let (draftable, bench): (Vec<usize>, Vec<usize>) = (0..players.len())
+            .filter(|i| players[*i].0 == position)
+            .partition(|i| count == 1 || points(*i) >= *replacement);
+
+        let points: Vec<f64> = draftable.iter().map(|i| points(*i)).collect();
+        let split = split(&points, count.saturating_sub(1).max(1));
+        let last = split.iter().max().map_or(1, |tier| tier + 1);
+        for (i, tier) in draftable.into_iter().zip(split) {
+            tiers[i] = Some(tier);
+        }
+        for i in bench {
+            tiers[i] = Some(last);
+        }
+    }
+    tiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_at_largest_gaps() {
+        let points = [30.0, 29.0, 20.0, 19.5, 10.0, 10.0, 2.0];
+        assert_eq!(split(&points, 3), [1, 1, 2, 2, 3, 3, 3]);
+        assert_eq!(split(&points, 1), [1; 7]);
+        // Input order is kept
+        assert_eq!(split(&[10.0, 30.0, 20.0], 3), [3, 1, 2]);
+    }
+
+    #[test]
+    fn never_splits_ties() {
+        assert_eq!(split(&[5.0, 5.0, 5.0], 3), [1, 1, 1]);
+        assert_eq!(split(&[9.0, 5.0, 5.0, 1.0], 4), [1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn respects_count() {
+        let points: Vec<f64> = (0..20).map(|i| f64::from(i * i)).collect();
+        for count in 1..=20 {
+            let tiers = split(&points, count);
+            assert_eq!(tiers.iter().max(), Some(&u32::from(count)));
+        }
+        assert_eq!(split(&[1.0, 2.0], 0), [1, 1]);
+    }
+
+    #[test]
+    fn empty_input() {
+        assert!(split(&[], 3).is_empty());
+        assert!(by_position(&[], &[], &BTreeMap::new(), 3).is_empty());
+    }
+
+    #[test]
+    fn bench_is_last_tier() {
+        let players = [
+            ("RB", 200.0),
+            ("RB", 100.0),
+            ("RB", 50.0),
+            ("RB", 10.0),
+            ("K", 90.0),
+        ];
+        let replacement = BTreeMap::from([("RB".to_string(), 100.0)]);
+        let tiers = by_position(&players, &[], &replacement, 3);
+        assert_eq!(tiers, [Some(1), Some(2), Some(3), Some(3), None]);
+    }
+
+    #[test]
+    fn tiers_on_projected_points() {
+        let players = [("RB", 200.0), ("RB", 190.0), ("RB", 50.0)];
+        let replacement = BTreeMap::from([("RB".to_string(), 40.0)]);
+        let season = by_position(&players, &[], &replacement, 3);
+        assert_eq!(season, [Some(1), Some(1), Some(2)]);
+        // The second player is projected to fall off, the last one is unprojected
+        let projected = by_position(&players, &[Some(210.0), Some(120.0)], &replacement, 3);
+        assert_eq!(projected, [Some(1), Some(2), Some(2)]);
+    }
+
+    #[test]
+    fn parses_projections() {
+        let projections =
+            Projections::from_csv("player_id,points\n00-1, 250.5\n\n00-2,80\n").unwrap();
+        assert_eq!(projections.get("00-1"), Some(250.5));
+        assert_eq!(projections.get("00-2"), Some(80.0));
+        assert_eq!(projections.get("00-3"), None);
+        assert!(matches!(
+            Projections::from_csv("00-1,lots"),
+            Err(ProjectionsError::Invalid { line: 1, .. })
+        ));
+        assert!(Projections::from_csv("00-1").is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use crate::{games::GameQuery, pbp::PbpQuery, plays::PlayQuery, teams::TeamQuery};
+
+/// Query builders that can be limited to a week or an inclusive range of weeks
+pub trait WeekFilter: Sized {
+    fn single_week(self, week: u16) -> Self;
+    fn week_range(self, start: u16, end: u16) -> Self;
+}
+
+impl WeekFilter for PbpQuery {
+    fn single_week(self, week: u16) -> Self {
+        self.filter_week(week)
+    }
+
+    fn week_range(self, start: u16, end: u16) -> Self {
+        self.filter_week_range(start, end)
+    }
+}
+
+impl WeekFilter for TeamQuery {
+    fn single_week(self, week: u16) -> Self {
+        TeamQuery::single_week(self, week)
+    }
+
+    fn week_range(self, start: u16, end: u16) -> Self {
+        TeamQuery::week_range(self, start, end)
+    }
+}
+
+impl WeekFilter for GameQuery {
+    fn single_week(self, week: u16) -> Self {
+        GameQuery::single_week(self, week)
+    }
+
+    fn week_range(self, start: u16, end: u16) -> Self {
+        GameQuery::week_range(self, start, end)
+    }
+}
+
+impl WeekFilter for PlayQuery {
+    fn single_week(self, week: u16) -> Self {
+        PlayQuery::single_week(self, week)
+    }
+
+    fn week_range(self, start: u16, end: u16) -> Self {
+        PlayQuery::week_range(self, start, end)
+    }
+}
@@ -0,0 +1,60 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// An inclusive range of seasons, e.g. `2024` or `2015-2024`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Years {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl Years {
+    pub fn new(start: u16, end: u16) -> Self {
+        Years { start, end }
+    }
+
+    pub fn contains(&self, year: u16) -> bool {
+        (self.start..=self.end).contains(&year)
+    }
+
+    /// The most recent season in the range
+    pub fn latest(&self) -> u16 {
+        self.end
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = u16> {
+        self.start..=self.end
+    }
+}
+
+impl FromStr for Years {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |y: &str| {
+            y.trim()
+                .parse::<u16>()
+                .map_err(|_| format!("invalid year: {y}"))
+        };
+        match s.split_once('-') {
+            None => parse(s).map(|y| Years::new(y, y)),
+            Some((start, end)) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start > end {
+                    return Err(format!("invalid year range: {s}"));
+                }
+                Ok(Years::new(start, end))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Years {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
@@ -2,11 +2,13 @@ import axios from 'axios';
 import toastStore from '$lib/stores/toasts.svelte';
 
 console.log('Init axios: ', import.meta.env.VITE_API_URL);
+const apiKey = import.meta.env.VITE_API_KEY;
 const axiosClient = axios.create({
 	baseURL: import.meta.env.VITE_API_URL,
 	headers: {
 		Accept: 'application/json',
-		'Content-Type': 'application/json'
+		'Content-Type': 'application/json',
+		...(apiKey ? { Authorization: `Bearer ${apiKey}` } : {})
 	}
 });
 
@@ -20,6 +22,8 @@ axiosClient.interceptors.response.use(
 		if (error.response?.status === 401) {
 			// window.location.href = '/login';
 			toastStore.addToast('Authentication error', 'error');
+		} else if (error.response?.status === 429) {
+			toastStore.addToast(data?.message ?? 'Rate limit exceeded', 'error');
 		} else if (error.response?.status === 500) {
 			toastStore.addToast('Internal server error.', 'error');
 		} else if (data?.message && typeof data?.message === 'string') {
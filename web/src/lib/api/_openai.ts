@@ -2,7 +2,7 @@ import OpenAI from 'openai';
 
 const openai = new OpenAI({
 	baseURL: (import.meta.env.VITE_API_URL ?? window.location.origin) + '/v1',
-	apiKey: '',
+	apiKey: import.meta.env.VITE_API_KEY ?? '',
 	dangerouslyAllowBrowser: true
 });
 
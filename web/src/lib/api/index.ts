@@ -14,6 +14,12 @@ export type StatsReq = {
 	team?: string;
 };
 
+/** Seasons the API has data for, latest first */
+export async function getYears(): Promise<number[]> {
+	const response = await axios.get('/v1/years');
+	return response.data;
+}
+
 export async function getStats(params: StatsReq): Promise<TableData> {
 	const response = await axios.get('/v1/stats', { params });
 	return response.data;
@@ -25,13 +31,100 @@ export async function searchPlayers(search: string): Promise<TableData> {
 	return response.data;
 }
 
+export type PlayerProfile = {
+	bio: Record<string, Scalar>;
+	seasons: TableData;
+	games: TableData;
+};
+
+export async function getPlayer(playerId: string): Promise<PlayerProfile> {
+	const response = await axios.get(`/v1/players/${encodeURIComponent(playerId)}`);
+	return response.data;
+}
+
+export type StatsQuery = {
+	year: number;
+	weeks: string | null;
+	player: string | null;
+	team: string | null;
+	position: string | null;
+	aggregate: boolean;
+	sort: string | null;
+	limit: number | null;
+};
+
+export type AskResponse = {
+	query: StatsQuery;
+	records: TableData;
+};
+
+// Interprets a question like "Who led RBs in rushing TDs weeks 4-8 of 2023?" into a stats query
+export async function ask(question: string): Promise<AskResponse> {
+	const response = await axios.post('/v1/ask', { question });
+	return response.data;
+}
+
+// Re-runs a query returned by `ask`, e.g. after the user edits it
+export async function runStatsQuery(query: StatsQuery): Promise<AskResponse> {
+	const response = await axios.post('/v1/ask', { query });
+	return response.data;
+}
+
+export type Conversation = {
+	id: string;
+	title: string;
+	created_by: string | null;
+	created_at: string;
+	updated_at: string;
+};
+
+export type ConversationMessage = {
+	id: number;
+	role: 'system' | 'user' | 'assistant';
+	content: string;
+	created_at: string;
+};
+
+export async function listConversations(): Promise<Conversation[]> {
+	const response = await axios.get('/v1/conversations');
+	return response.data;
+}
+
+export async function createConversation(title?: string): Promise<Conversation> {
+	const response = await axios.post('/v1/conversations', { title });
+	return response.data;
+}
+
+export async function getConversation(
+	id: string
+): Promise<Conversation & { messages: ConversationMessage[] }> {
+	const response = await axios.get(`/v1/conversations/${id}`);
+	return response.data;
+}
+
+export async function renameConversation(id: string, title: string): Promise<Conversation> {
+	const response = await axios.patch(`/v1/conversations/${id}`, { title });
+	return response.data;
+}
+
+export async function deleteConversation(id: string): Promise<void> {
+	await axios.delete(`/v1/conversations/${id}`);
+}
+
 // messages: [{ role: 'user', content: 'Say this is a test' }]
-export async function* streamChat(messages: OpenAI.ChatCompletionMessageParam[]) {
-	const stream = await openai.chat.completions.create({
-		model: 'openai-with-spice',
-		messages,
-		stream: true
-	});
+// If a conversation id is given, the latest user message and the answer are saved to it
+export async function* streamChat(
+	messages: OpenAI.ChatCompletionMessageParam[],
+	conversation?: string
+) {
+	const stream = await openai.chat.completions.create(
+		{
+			model: 'openai-with-fff',
+			messages,
+			stream: true
+		},
+		{ query: conversation ? { conversation } : undefined }
+	);
 
 	for await (const chunk of stream) {
 		const content = chunk.choices[0]?.delta?.content || '';